@@ -0,0 +1,18 @@
+use std::io::BufRead;
+
+// files handed to options like --words share one layout: blank lines and lines
+// starting with # are skipped, the rest come back trimmed with their 1-indexed
+// line number
+pub fn entries<R: BufRead>(input: R) -> impl Iterator<Item = Result<(usize, String), std::io::Error>> {
+    input.lines().enumerate().filter_map(|(index, line)| match line {
+        Ok(line) => {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                None
+            } else {
+                Some(Ok((index + 1, trimmed.to_owned())))
+            }
+        },
+        Err(e) => Some(Err(e)),
+    })
+}
@@ -1,48 +1,399 @@
-use std::{io::{BufReader, BufRead}, fs::File, collections::HashMap};
+use std::{io::{BufReader, BufRead}, fs::File, collections::HashMap, fmt::Display, error::Error, str::FromStr};
 
+use clap::Args;
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use regex::Regex;
+
+use crate::number_theory::{self, CrtError};
+
+#[derive(Args)]
+#[group(id = "day_eight")]
+pub struct Options {
+    /// Day eight: start signposts, as exact:NAME, suffix:S, prefix:P or regex:RE (repeatable)
+    #[clap(long = "start")]
+    start: Vec<SignpostMatcher>,
+    /// Day eight: goal signposts, in the same form as --start (repeatable)
+    #[clap(long = "goal")]
+    goal: Vec<SignpostMatcher>,
+}
+
+// marks a node that has been referenced as a target but never declared with its own line
+const UNDECLARED: u32 = u32::MAX;
 
 struct Map {
-    inner: HashMap<(Signpost, Direction), Signpost>
+    names: Vec<String>,
+    ids: HashMap<String, Signpost>,
+    // flat adjacency, indexed by signpost id then by direction
+    adjacency: Vec<[u32; 2]>,
 }
 
 impl Map {
-    fn parse(lines: &[String]) -> Map {
+    fn parse<I: Iterator<Item = String>>(lines: I) -> Map {
         let mut m = Map {
-            inner: HashMap::new(),
+            names: Vec::new(),
+            ids: HashMap::new(),
+            adjacency: Vec::new(),
         };
 
         for ele in lines {
-            m.parse_line(ele.clone());
+            if ele.is_empty() {
+                continue;
+            }
+            m.parse_line(&ele);
         }
 
         m
     }
 
-    fn parse_line(&mut self, line: String) {
-        let parts: Vec<&str> = line.split(" = ").collect();
+    fn parse_line(&mut self, line: &str) {
+        let (current, go_to) = line.split_once(" = ").expect("no =");
+
+        let current_signpost = self.intern(current);
+
+        let (left, right) = go_to.strip_prefix('(').expect("no (").strip_suffix(')').expect("no )").split_once(", ").expect("no ,");
+
+        let left = self.intern(left);
+        let right = self.intern(right);
+
+        self.adjacency[current_signpost.id as usize] = [left.id, right.id];
+    }
+
+    fn intern(&mut self, name: &str) -> Signpost {
+        if let Some(signpost) = self.ids.get(name) {
+            return *signpost;
+        }
+
+        let signpost = Signpost { id: u32::try_from(self.names.len()).expect("too many signposts") };
+        assert!(signpost.id != UNDECLARED, "too many signposts");
+
+        self.names.push(name.to_owned());
+        self.ids.insert(name.to_owned(), signpost);
+        self.adjacency.push([UNDECLARED; 2]);
+
+        signpost
+    }
+
+    fn lookup(&self, name: &str) -> Option<Signpost> {
+        self.ids.get(name).copied()
+    }
+
+    fn name(&self, signpost: Signpost) -> &str {
+        &self.names[signpost.id as usize]
+    }
+
+    fn navigate(&self, signpost: Signpost, direction: Direction) -> Signpost {
+        let id = self.adjacency[signpost.id as usize][direction as usize];
+        assert!(id != UNDECLARED, "boo no signpost!");
+
+        Signpost { id }
+    }
+
+    fn signposts(&self) -> impl Iterator<Item = Signpost> + '_ {
+        (0..self.names.len() as u32).map(|id| Signpost { id })
+    }
+
+    // resolves a matcher set into a per-signpost flag, failing on exact names that don't exist
+    fn matching(&self, matchers: &[SignpostMatcher]) -> Result<Vec<bool>, NetworkError> {
+        for matcher in matchers {
+            if let SignpostMatcher::Exact(name) = matcher {
+                if self.lookup(name).is_none() {
+                    return Err(NetworkError::MissingSignpost(name.clone()));
+                }
+            }
+        }
+
+        Ok(self.names.iter().map(|name| matchers.iter().any(|m| m.matches(name))).collect())
+    }
+
+    fn get_starting_signs(&self, matchers: &[SignpostMatcher]) -> Result<Vec<Signpost>, NetworkError> {
+        let is_start = self.matching(matchers)?;
+        let starts: Vec<Signpost> = self.signposts().filter(|x| is_start[x.id as usize]).collect();
+
+        if starts.is_empty() {
+            return Err(NetworkError::NoStartingSigns);
+        }
+
+        Ok(starts)
+    }
+
+    fn validate(&self) -> Result<(), NetworkError> {
+        for signpost in self.signposts() {
+            for target in self.adjacency[signpost.id as usize] {
+                if target == UNDECLARED {
+                    continue;
+                }
+
+                if self.adjacency[target as usize][0] == UNDECLARED {
+                    return Err(NetworkError::MissingTarget {
+                        from: self.name(signpost).to_owned(),
+                        target: self.name(Signpost { id: target }).to_owned(),
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn can_reach<F: Fn(Signpost) -> bool>(&self, start: Signpost, is_goal: F) -> bool {
+        let mut seen = vec![false; self.names.len()];
+        let mut stack = vec![start];
+        seen[start.id as usize] = true;
+
+        while let Some(signpost) = stack.pop() {
+            if is_goal(signpost) {
+                return true;
+            }
+
+            for target in self.adjacency[signpost.id as usize] {
+                if target != UNDECLARED && !seen[target as usize] {
+                    seen[target as usize] = true;
+                    stack.push(Signpost { id: target });
+                }
+            }
+        }
+
+        false
+    }
+
+    fn step(&self, state: (Signpost, usize), directions: &[Direction]) -> (Signpost, usize) {
+        let next_index = if state.1 + 1 >= directions.len() { 0 } else { state.1 + 1 };
+        (self.navigate(state.0, directions[state.1]), next_index)
+    }
+
+    fn walk<F: Fn(Signpost) -> bool>(&self, start: Signpost, directions: &[Direction], is_goal: F) -> Result<usize, NetworkError> {
+        self.walk_from((start, 0), directions, is_goal).map(|(num_steps, _)| num_steps)
+    }
+
+    // walks with brent's cycle detection over (signpost, direction index) so
+    // a loop that never hits the goal is reported instead of spinning forever.
+    // returns the number of steps taken and the state the goal was reached in
+    fn walk_from<F: Fn(Signpost) -> bool>(&self, start_state: (Signpost, usize), directions: &[Direction], is_goal: F) -> Result<(usize, (Signpost, usize)), NetworkError> {
+        if directions.is_empty() {
+            return Err(NetworkError::NoDirections);
+        }
+
+        let start = start_state.0;
+        if !self.can_reach(start, &is_goal) {
+            return Err(NetworkError::Unreachable { start: self.name(start).to_owned() });
+        }
+
+        let mut tortoise = start_state;
+        let mut hare = start_state;
+        let mut power = 1;
+        let mut lambda = 0;
+        let mut num_steps = 0;
+
+        loop {
+            if is_goal(hare.0) {
+                return Ok((num_steps, hare));
+            }
+
+            if power == lambda {
+                tortoise = hare;
+                power *= 2;
+                lambda = 0;
+            }
+
+            hare = self.step(hare, directions);
+            lambda += 1;
+            num_steps += 1;
+
+            if hare == tortoise {
+                break;
+            }
+        }
+
+        let (mu, entry) = self.loop_entry(start_state, directions, lambda);
+
+        Err(NetworkError::Cycle {
+            start: self.name(start).to_owned(),
+            entry: self.name(entry.0).to_owned(),
+            entry_direction_index: entry.1,
+            steps_before_loop: mu,
+            loop_length: lambda,
+        })
+    }
+
+    // brent's cycle detection without stopping at goals, as (mu, lambda): the
+    // walk enters its loop after mu steps and repeats every lambda steps
+    fn state_cycle(&self, start_state: (Signpost, usize), directions: &[Direction]) -> (usize, usize) {
+        let mut tortoise = start_state;
+        let mut hare = self.step(start_state, directions);
+        let mut power = 1;
+        let mut lambda = 1;
+
+        while tortoise != hare {
+            if power == lambda {
+                tortoise = hare;
+                power *= 2;
+                lambda = 0;
+            }
+
+            hare = self.step(hare, directions);
+            lambda += 1;
+        }
+
+        (self.loop_entry(start_state, directions, lambda).0, lambda)
+    }
+
+    // finds where the loop is entered once its length is known, as the number
+    // of steps before the loop and the state it is entered in
+    fn loop_entry(&self, start_state: (Signpost, usize), directions: &[Direction], lambda: usize) -> (usize, (Signpost, usize)) {
+        let mut tortoise = start_state;
+        let mut hare = start_state;
+        for _ in 0..lambda {
+            hare = self.step(hare, directions);
+        }
+
+        let mut mu = 0;
+        while tortoise != hare {
+            tortoise = self.step(tortoise, directions);
+            hare = self.step(hare, directions);
+            mu += 1;
+        }
 
-        let current_signpost = Signpost::parse(parts[0]);
+        (mu, tortoise)
+    }
 
-        let go_to_signs: Vec<&str> = parts[1].strip_prefix('(').expect("no (").strip_suffix(')').expect("no )").split(", ").collect();
+    // every step count at which the walk from start stands on a goal. the walk
+    // is periodic over (signpost, direction index), so one pass through the
+    // steps before the loop and one lap of it covers them all
+    fn goal_times<F: Fn(Signpost) -> bool>(&self, start: Signpost, directions: &[Direction], is_goal: F) -> Result<GoalTimes, NetworkError> {
+        // reports unreachable goals and loops that never touch one
+        self.walk_from((start, 0), directions, &is_goal)?;
+
+        let (loop_start, loop_length) = self.state_cycle((start, 0), directions);
+        let mut times = GoalTimes { before_loop: Vec::new(), loop_start, loop_length, in_loop: Vec::new() };
+
+        let mut state = (start, 0);
+        for steps in 0..loop_start + loop_length {
+            if is_goal(state.0) {
+                if steps < loop_start {
+                    times.before_loop.push(steps);
+                } else {
+                    times.in_loop.push(steps);
+                }
+            }
+            state = self.step(state, directions);
+        }
 
-        self.inner.insert((current_signpost.clone(), Direction::Left), Signpost::parse(go_to_signs[0]));
-        self.inner.insert((current_signpost, Direction::Right), Signpost::parse(go_to_signs[1]));
+        Ok(times)
     }
-    
-    fn navigate(&self, goto: &(Signpost, Direction)) -> Signpost {
-        self.inner.get(goto).expect("boo no signpost!").clone()
+}
+
+// the steps at which one ghost stands on a goal: the hits before its walk
+// settles into its loop, then the hits in one lap, which recur every loop_length
+struct GoalTimes {
+    before_loop: Vec<usize>,
+    loop_start: usize,
+    loop_length: usize,
+    in_loop: Vec<usize>,
+}
+
+impl GoalTimes {
+    fn contains(&self, steps: usize) -> bool {
+        if steps < self.loop_start {
+            self.before_loop.contains(&steps)
+        } else {
+            let lap = (steps - self.loop_start) % self.loop_length;
+            self.in_loop.iter().any(|x| x - self.loop_start == lap)
+        }
+    }
+}
+
+// the first step at which every ghost stands on a goal at once
+fn earliest_common(ghosts: &[GoalTimes]) -> Result<i128, NetworkError> {
+    // the usual case: every ghost hits a goal exactly once per lap of its loop,
+    // a whole lap after it starts, so they line up at the lcm of the laps
+    if ghosts.iter().all(|x| x.before_loop.is_empty() && x.in_loop == [x.loop_length]) {
+        let steps = number_theory::lcm(ghosts.iter().map(|x| x.loop_length as u128)).ok_or(NetworkError::Overflow)?;
+        return i128::try_from(steps).map_err(|_| NetworkError::Overflow);
+    }
+
+    // before every ghost is in its loop the hits don't repeat, so check each step
+    let settled = ghosts.iter().map(|x| x.loop_start).max().unwrap_or(0);
+    if let Some(steps) = (0..settled).find(|steps| ghosts.iter().all(|x| x.contains(*steps))) {
+        return Ok(steps as i128);
+    }
+
+    // after that, one congruence per choice of in-loop hit for each ghost
+    let mut congruences = vec![(0, 1)];
+    for ghost in ghosts {
+        let mut combined = Vec::new();
+        for congruence in &congruences {
+            for hit in &ghost.in_loop {
+                match number_theory::crt([*congruence, (*hit as i128, ghost.loop_length as i128)]) {
+                    Ok(solution) => combined.push(solution),
+                    Err(CrtError::NoSolution) => (),
+                    Err(_) => return Err(NetworkError::Overflow),
+                }
+            }
+        }
+        combined.sort_unstable();
+        combined.dedup();
+        congruences = combined;
+    }
+
+    // the smallest solution of each that isn't before every ghost has settled
+    let settled = settled as i128;
+    let mut earliest: Option<i128> = None;
+    for (steps, period) in congruences {
+        let steps = if steps < settled {
+            let periods = (settled - steps + period - 1) / period;
+            periods.checked_mul(period).and_then(|x| x.checked_add(steps)).ok_or(NetworkError::Overflow)?
+        } else {
+            steps
+        };
+        earliest = Some(earliest.map_or(steps, |x| x.min(steps)));
+    }
+
+    earliest.ok_or(NetworkError::NeverAligned)
+}
+
+#[derive(Debug)]
+enum NetworkError {
+    MissingTarget { from: String, target: String },
+    MissingSignpost(String),
+    NoStartingSigns,
+    NoDirections,
+    Unreachable { start: String },
+    NeverAligned,
+    Overflow,
+    Cycle { start: String, entry: String, entry_direction_index: usize, steps_before_loop: usize, loop_length: usize },
+}
+
+impl Display for NetworkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingTarget { from, target } => write!(f, "signpost {} points at {}, which is never declared", from, target),
+            Self::MissingSignpost(name) => write!(f, "signpost {} is not in the network", name),
+            Self::NoStartingSigns => write!(f, "no starting signposts in the network"),
+            Self::NoDirections => write!(f, "no directions to follow"),
+            Self::Unreachable { start } => write!(f, "no goal signpost is reachable from {}", start),
+            Self::NeverAligned => write!(f, "ghosts never reach their goals at the same time"),
+            Self::Overflow => write!(f, "step count overflowed"),
+            Self::Cycle { start, entry, entry_direction_index, steps_before_loop, loop_length } => write!(
+                f,
+                "walking from {} never reaches a goal: after {} steps it loops every {} steps, entering at {} on direction {}",
+                start, steps_before_loop, loop_length, entry, entry_direction_index
+            ),
+        }
     }
+}
+
+impl Error for NetworkError {}
 
-    fn get_starting_signs(&self) -> Vec<Signpost> {
-        self.inner.keys().filter(|x| x.0.is_a_sign()).map(|x| &x.0).cloned().collect()
+impl From<NetworkError> for std::io::Error {
+    fn from(value: NetworkError) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, value)
     }
 }
 
 #[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
 enum Direction {
-    Left,
-    Right
+    Left = 0,
+    Right = 1,
 }
 
 impl Direction {
@@ -55,97 +406,137 @@ impl Direction {
     }
 }
 
-#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
 struct Signpost {
-    inner: String
+    id: u32
 }
 
-impl Signpost {
-    fn parse(val: &str) -> Signpost {
-        Signpost { inner: val.to_owned() }
-    }
+#[derive(Clone)]
+enum SignpostMatcher {
+    Exact(String),
+    Suffix(String),
+    Prefix(String),
+    Regex(Regex),
+}
 
-    fn is_a_sign(&self) -> bool {
-        self.inner.ends_with('A')
+impl SignpostMatcher {
+    fn matches(&self, name: &str) -> bool {
+        match self {
+            Self::Exact(exact) => name == exact,
+            Self::Suffix(suffix) => name.ends_with(suffix.as_str()),
+            Self::Prefix(prefix) => name.starts_with(prefix.as_str()),
+            Self::Regex(regex) => regex.is_match(name),
+        }
     }
+}
+
+impl FromStr for SignpostMatcher {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, pattern) = s.split_once(':').ok_or_else(|| format!("expected kind:pattern, got {}", s))?;
 
-    fn is_z_sign(&self) -> bool {
-        self.inner.ends_with('Z')
+        match kind {
+            "exact" => Ok(Self::Exact(pattern.to_owned())),
+            "suffix" => Ok(Self::Suffix(pattern.to_owned())),
+            "prefix" => Ok(Self::Prefix(pattern.to_owned())),
+            "regex" => Regex::new(pattern).map(Self::Regex).map_err(|e| e.to_string()),
+            _ => Err(format!("unknown matcher kind {}, expected exact, suffix, prefix or regex", kind)),
+        }
     }
 }
 
-pub fn question_one(in_file: BufReader<File>) -> Result<(), std::io::Error> {
-    let lines: Vec<String> = in_file.lines().map(|x| x.expect("boo! bad string")).collect();
-    let directions: Vec<Direction> = lines[0].chars().map(Direction::parse).collect();
+fn parse_input(in_file: BufReader<File>) -> Result<(Vec<Direction>, Map), std::io::Error> {
+    let mut lines = in_file.lines();
+    let directions: Vec<Direction> = lines.next().expect("boo! no directions")?.chars().map(Direction::parse).collect();
 
-    let map = Map::parse(&lines[2..]);
+    let map = Map::parse(lines.map(|x| x.expect("boo! bad string")));
+    map.validate()?;
 
-    let mut current_signpost = Signpost::parse("AAA");
-    let mut direction_index = 0;
-    let mut num_steps = 0;
+    Ok((directions, map))
+}
 
-    while current_signpost.inner.as_str() != "ZZZ" {
-        current_signpost = map.navigate(&(current_signpost, directions[direction_index]));
-        direction_index += 1;
+fn matchers_or(matchers: &[SignpostMatcher], default: SignpostMatcher) -> Vec<SignpostMatcher> {
+    if matchers.is_empty() {
+        vec![default]
+    } else {
+        matchers.to_vec()
+    }
+}
 
-        if direction_index >= directions.len() {
-            direction_index = 0;
-        }
+pub fn question_one(in_file: BufReader<File>, options: &Options) -> Result<(), std::io::Error> {
+    let (directions, map) = parse_input(in_file)?;
 
-        num_steps += 1;
-    }
+    let starts = map.get_starting_signs(&matchers_or(&options.start, SignpostMatcher::Exact(String::from("AAA"))))?;
+    let is_goal = map.matching(&matchers_or(&options.goal, SignpostMatcher::Exact(String::from("ZZZ"))))?;
+
+    for start in starts {
+        let num_steps = map.walk(start, &directions, |x| is_goal[x.id as usize])?;
 
-    println!("steps: {}", num_steps);
+        if options.start.is_empty() {
+            println!("steps: {}", num_steps);
+        } else {
+            println!("{} steps: {}", map.name(start), num_steps);
+        }
+    }
 
     Ok(())
 }
 
-pub fn question_two(in_file: BufReader<File>) -> Result<(), std::io::Error> {
-    let lines: Vec<String> = in_file.lines().map(|x| x.expect("boo! bad string")).collect();
-    let directions: Vec<Direction> = lines[0].chars().map(Direction::parse).collect();
+// the first step at which every ghost walking from a start stands on a goal
+fn ghost_steps(map: &Map, directions: &[Direction], start: &[SignpostMatcher], goal: &[SignpostMatcher]) -> Result<i128, NetworkError> {
+    let current_signposts = map.get_starting_signs(start)?;
+    let is_goal = map.matching(goal)?;
 
-    let map = Map::parse(&lines[2..]);
+    let goal_times: Vec<GoalTimes> = current_signposts.par_iter().map(|signpost| {
+        map.goal_times(*signpost, directions, |x| is_goal[x.id as usize])
+    }).collect::<Result<_, _>>()?;
 
-    let current_signposts = map.get_starting_signs();
-
-        let resolved_signposts: Vec<(Signpost, usize)> = current_signposts.par_iter().map(|signpost| {
-            let mut direction_index = 0;
-            let mut current_signpost = signpost.clone();
-            let mut num_steps: usize = 0;
-            while !current_signpost.is_z_sign() {
-                current_signpost = map.navigate(&(current_signpost, directions[direction_index]));
-                direction_index += 1;
-        
-                if direction_index >= directions.len() {
-                    direction_index = 0;
-                }
-        
-                num_steps += 1;
-            }
-            (current_signpost, num_steps)
-        }).collect();
+    earliest_common(&goal_times)
+}
 
-    let common_steps: Vec<usize> = resolved_signposts.iter().map(|x| x.1).collect();
-    let lcm = lcm(&common_steps);
+pub fn question_two(in_file: BufReader<File>, options: &Options) -> Result<(), std::io::Error> {
+    let (directions, map) = parse_input(in_file)?;
 
-    println!("steps: {}", lcm);
+    let start = matchers_or(&options.start, SignpostMatcher::Suffix(String::from("A")));
+    let goal = matchers_or(&options.goal, SignpostMatcher::Suffix(String::from("Z")));
+    let steps = ghost_steps(&map, &directions, &start, &goal)?;
+    println!("steps: {}", steps);
 
     Ok(())
 }
 
-// shamelessly stolen as I am not good at the maths
-pub fn lcm(nums: &[usize]) -> usize {
-    if nums.len() == 1 {
-        return nums[0];
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ghost_steps_in(text: &str) -> Result<i128, NetworkError> {
+        let mut lines = text.lines();
+        let directions: Vec<Direction> = lines.next().unwrap().chars().map(Direction::parse).collect();
+        let map = Map::parse(lines.map(str::to_owned));
+
+        ghost_steps(&map, &directions, &[SignpostMatcher::Suffix(String::from("A"))], &[SignpostMatcher::Suffix(String::from("Z"))])
+    }
+
+    #[test]
+    fn part_two_example() {
+        let text = "LR\n\n11A = (11B, XXX)\n11B = (XXX, 11Z)\n11Z = (11B, XXX)\n22A = (22B, XXX)\n22B = (22C, 22C)\n22C = (22Z, 22Z)\n22Z = (22B, 22B)\nXXX = (XXX, XXX)\n";
+        assert_eq!(ghost_steps_in(text).unwrap(), 6);
+    }
+
+    #[test]
+    fn part_one_examples() {
+        let text = "RL\n\nAAA = (BBB, CCC)\nBBB = (DDD, EEE)\nCCC = (ZZZ, GGG)\nDDD = (DDD, DDD)\nEEE = (EEE, EEE)\nGGG = (GGG, GGG)\nZZZ = (ZZZ, ZZZ)\n";
+        assert_eq!(ghost_steps_in(text).unwrap(), 2);
+
+        let text = "LLR\n\nAAA = (BBB, BBB)\nBBB = (AAA, ZZZ)\nZZZ = (ZZZ, ZZZ)\n";
+        assert_eq!(ghost_steps_in(text).unwrap(), 6);
     }
-    let a = nums[0];
-    let b = lcm(&nums[1..]);
-    a * b / gcd(a, b)
-}
 
-fn gcd(a: usize, b: usize) -> usize {
-    if b == 0 {
-        return a;
+    #[test]
+    fn ghosts_out_of_phase() {
+        // 11A is on 11Z at every odd step, 22A is on 22Z at every even step
+        let text = "L\n\n11A = (11Z, 11Z)\n11Z = (11B, 11B)\n11B = (11Z, 11Z)\n22A = (22B, 22B)\n22B = (22Z, 22Z)\n22Z = (22B, 22B)\n";
+        assert!(matches!(ghost_steps_in(text), Err(NetworkError::NeverAligned)));
     }
-    gcd(b, a % b)
 }
@@ -1,7 +1,6 @@
 use std::{io::{BufReader, BufRead}, fs::File, collections::HashMap};
 
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
-use rust_lapper::{Lapper, Interval};
 
 #[derive(Clone)]
 struct IntervalStorage {
@@ -1,109 +1,384 @@
-use std::{io::{BufReader, BufRead}, fs::File, collections::HashMap};
+use std::{io::{BufReader, BufRead}, fs::File, collections::{HashMap, HashSet}, time::Instant, fmt::Display, error::Error};
+
+use clap::{Args, ValueEnum};
+use num_bigint::BigUint;
+
+use crate::config;
+
+#[derive(Args)]
+#[group(id = "day_four")]
+pub struct Options {
+    /// Day four: time the bitset card matching against the hash map matching over this many runs
+    #[clap(long = "match-bench")]
+    match_bench: Option<u32>,
+    /// Day four: count card copies with arbitrary-precision integers instead of failing when a u64 overflows
+    #[clap(long = "big-counts")]
+    big_counts: bool,
+    /// Day four: points a card scores for its matches in question one
+    #[clap(long = "scoring", value_enum, default_value_t = ScoringRule::Doubling)]
+    scoring: ScoringRule,
+    /// Day four: read the points for each number of matches from a file of "MATCHES POINTS" lines instead
+    #[clap(long = "score-table", conflicts_with = "scoring")]
+    score_table: Option<String>,
+    /// Day four: which cards a card's matches win copies of in question two
+    #[clap(long = "copies", value_enum, default_value_t = CopyRule::Next)]
+    copies: CopyRule,
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+enum ScoringRule {
+    // 1, 2, 4, 8, ...
+    Doubling,
+    // 1, 2, 3, 4, ...
+    Linear,
+    // 1, 1, 2, 3, 5, ...
+    Fibonacci,
+}
+
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq)]
+enum CopyRule {
+    // the next N cards, wins past the last card are lost
+    Next,
+    // the next N cards, wins past the last card become extra copies of it
+    Capped,
+    // the next N cards, carrying on from the first card after the last
+    Wraparound,
+}
+
+#[derive(Debug)]
+enum ScoreError {
+    Overflow { card: i64 },
+    MissingPoints { card: i64, matches: i64 },
+    Malformed { line: usize, text: String },
+}
+
+impl Display for ScoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Overflow { card } => write!(f, "card {}: score overflowed an i64", card),
+            Self::MissingPoints { card, matches } => write!(f, "card {}: score table has no entry for {} matches", card, matches),
+            Self::Malformed { line, text } => write!(f, "line {}: expected \"MATCHES POINTS\", found \"{}\"", line, text),
+        }
+    }
+}
+
+impl Error for ScoreError {}
+
+impl From<ScoreError> for std::io::Error {
+    fn from(value: ScoreError) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, value)
+    }
+}
+
+enum Scoring {
+    Rule(ScoringRule),
+    // points by number of matches, no matches scores nothing unless the table says otherwise
+    Table(HashMap<i64, i64>),
+}
+
+impl Scoring {
+    fn from_options(options: &Options) -> Result<Scoring, std::io::Error> {
+        match &options.score_table {
+            Some(path) => Scoring::from_file(BufReader::new(File::open(path)?)),
+            None => Ok(Scoring::Rule(options.scoring)),
+        }
+    }
+
+    fn from_file(in_file: BufReader<File>) -> Result<Scoring, std::io::Error> {
+        let mut table = HashMap::new();
+
+        for entry in config::entries(in_file) {
+            let (line, text) = entry?;
+            let entry = match text.split_whitespace().map(str::parse::<i64>).collect::<Vec<_>>()[..] {
+                [Ok(matches), Ok(points)] if matches >= 0 => (matches, points),
+                _ => return Err(ScoreError::Malformed { line, text }.into()),
+            };
+            table.insert(entry.0, entry.1);
+        }
+
+        Ok(Scoring::Table(table))
+    }
+
+    fn points(&self, card: &Card) -> Result<i64, ScoreError> {
+        let matches = card.num_matchs();
+        let overflow = || ScoreError::Overflow { card: card.card_id };
+
+        match self {
+            Self::Table(table) => match table.get(&matches) {
+                Some(points) => Ok(*points),
+                None if matches == 0 => Ok(0),
+                None => Err(ScoreError::MissingPoints { card: card.card_id, matches }),
+            },
+            Self::Rule(_) if matches == 0 => Ok(0),
+            Self::Rule(ScoringRule::Doubling) => 2i64.checked_pow(matches as u32 - 1).ok_or_else(overflow),
+            Self::Rule(ScoringRule::Linear) => Ok(matches),
+            Self::Rule(ScoringRule::Fibonacci) => {
+                let (mut previous, mut current): (i64, i64) = (0, 1);
+                for _ in 1..matches {
+                    (previous, current) = (current, previous.checked_add(current).ok_or_else(overflow)?);
+                }
+
+                Ok(current)
+            },
+        }
+    }
+}
+
+#[derive(Debug)]
+enum CascadeError {
+    Overflow { card: i64 },
+    // wraparound wins lead back to this card, so its copies never stop
+    Unbounded { card: i64 },
+}
+
+impl Display for CascadeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Overflow { card } => write!(f, "card {}: copy count overflowed a u64, try --big-counts", card),
+            Self::Unbounded { card } => write!(f, "card {}: wins copies of itself through the wraparound, so there is no end", card),
+        }
+    }
+}
+
+impl Error for CascadeError {}
+
+impl From<CascadeError> for std::io::Error {
+    fn from(value: CascadeError) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, value)
+    }
+}
+
+// numbers below 128 are bits in a u128, anything else goes in a hash set.
+// repeats are kept so a number listed twice still matches twice
+#[derive(Clone, Default)]
+struct NumberSet {
+    bits: u128,
+    large: HashSet<i64>,
+    repeats: Vec<i64>,
+}
+
+impl NumberSet {
+    fn insert(&mut self, number: i64) {
+        if self.contains(number) {
+            self.repeats.push(number);
+        } else if (0..128).contains(&number) {
+            self.bits |= 1 << number;
+        } else {
+            self.large.insert(number);
+        }
+    }
+
+    fn contains(&self, number: i64) -> bool {
+        if (0..128).contains(&number) {
+            self.bits & (1 << number) != 0
+        } else {
+            self.large.contains(&number)
+        }
+    }
+
+    // how many of our numbers (repeats included) are in the other set
+    fn matches(&self, other: &NumberSet) -> i64 {
+        let small = (self.bits & other.bits).count_ones() as i64;
+        let large = self.large.iter().filter(|x| other.large.contains(x)).count() as i64;
+        let repeats = self.repeats.iter().filter(|x| other.contains(**x)).count() as i64;
+
+        small + large + repeats
+    }
+}
+
+impl FromIterator<i64> for NumberSet {
+    fn from_iter<T: IntoIterator<Item = i64>>(iter: T) -> Self {
+        let mut set = NumberSet::default();
+        for number in iter {
+            set.insert(number);
+        }
+
+        set
+    }
+}
 
 #[derive(Clone)]
 struct Card {
     card_id: i64,
-    winning_numbers: HashMap<i64, ()>,
-    my_numbers: Vec<i64>
+    winning_numbers: NumberSet,
+    my_numbers: NumberSet,
+}
+
+fn parse_numbers(numbers: &str) -> Vec<i64> {
+    numbers.split_whitespace().map(|x| str::parse::<i64>(x).expect("failed to parse")).collect()
+}
+
+fn split_card(line: &str) -> (i64, Vec<i64>, Vec<i64>) {
+    let chunks = line.split(':').collect::<Vec<&str>>();
+
+    let card_splits = chunks[0].split(' ').collect::<Vec<&str>>();
+    let card_id = str::parse::<i64>(card_splits[card_splits.len()-1]).expect("failed to extract card id");
+
+    let number_splits = chunks[1].split('|').collect::<Vec<&str>>();
+
+    (card_id, parse_numbers(number_splits[0]), parse_numbers(number_splits[1]))
 }
 
 impl Card {
     fn new(line: &str) -> Card {
-        let chunks = line.split(':').collect::<Vec<&str>>();
-    
-        let card_splits = chunks[0].split(' ').collect::<Vec<&str>>();
-        let card_id = str::parse::<i64>(card_splits[card_splits.len()-1]).expect("failed to extract card id");
-        
-        let number_splits = chunks[1].split('|').collect::<Vec<&str>>();
+        let (card_id, winning_numbers, my_numbers) = split_card(line);
 
-        let winning_numbers = number_splits[0].strip_prefix(' ').unwrap_or(number_splits[0]).split_whitespace().map(|x| str::parse::<i64>(x).expect("failed to parse")).collect::<Vec<i64>>();
-        let my_numbers= number_splits[1].strip_prefix(' ').unwrap_or(number_splits[1]).split_whitespace().map(|x| str::parse::<i64>(x).expect("failed to parse")).collect::<Vec<i64>>();
+        Card { card_id, winning_numbers: winning_numbers.into_iter().collect(), my_numbers: my_numbers.into_iter().collect() }
+    }
 
-        let mut winning_number_set = HashMap::new();
+    fn num_matchs(&self) -> i64 {
+        self.my_numbers.matches(&self.winning_numbers)
+    }
+}
 
-        for number in winning_numbers {
-            winning_number_set.insert(number, ());
-        }
-    
-        Card { card_id, winning_numbers: winning_number_set, my_numbers }
+// the original matching, a hash map of winning numbers probed with each of ours
+fn hash_map_matches(winning_numbers: &[i64], my_numbers: &[i64]) -> i64 {
+    let winning_number_set: HashMap<i64, ()> = winning_numbers.iter().map(|x| (*x, ())).collect();
+
+    my_numbers.iter().filter(|x| winning_number_set.contains_key(x)).count() as i64
+}
+
+fn bench(lines: &[String], iterations: u32) {
+    let start = Instant::now();
+    let mut hash_result = 0;
+    for _ in 0..iterations {
+        hash_result = lines.iter().map(|x| {
+            let (_, winning_numbers, my_numbers) = split_card(x);
+            hash_map_matches(&winning_numbers, &my_numbers)
+        }).sum::<i64>();
     }
+    let hash_time = start.elapsed();
 
-    fn score(&self) -> i64 {
-        let mut score: i64 = 0;
-        let mut num_matchs = 0;
+    let start = Instant::now();
+    let mut bitset_result = 0;
+    for _ in 0..iterations {
+        bitset_result = lines.iter().map(|x| Card::new(x).num_matchs()).sum::<i64>();
+    }
+    let bitset_time = start.elapsed();
 
-        for number in &self.my_numbers {
-            if self.winning_numbers.contains_key(number) {
-                if score == 0 {
-                    score = 1;
-                } else {
-                    num_matchs += 1;
-                }
+    println!("hash map: {} matches in {:?} ({:?} per run)", hash_result, hash_time, hash_time / iterations);
+    println!("bitset: {} matches in {:?} ({:?} per run)", bitset_result, bitset_time, bitset_time / iterations);
+}
+
+impl CopyRule {
+    // indices of the cards each card wins a copy of, one entry per copy
+    fn targets(self, cards: &[Card]) -> Vec<Vec<usize>> {
+        let count = cards.len();
+
+        cards.iter().enumerate().map(|(index, card)| {
+            let won = (index + 1)..=(index + card.num_matchs() as usize);
+
+            match self {
+                Self::Next => won.filter(|x| *x < count).collect(),
+                // the last card would only be winning copies of itself
+                Self::Capped if index + 1 == count => Vec::new(),
+                Self::Capped => won.map(|x| x.min(count - 1)).collect(),
+                Self::Wraparound => won.map(|x| x % count).collect(),
             }
-        }
+        }).collect()
+    }
 
-        for _ in 0..num_matchs {
-            score *= 2;
+    // an order where every card comes after all the cards that win copies of it
+    fn order(self, cards: &[Card], targets: &[Vec<usize>]) -> Result<Vec<usize>, CascadeError> {
+        // everything else only wins cards further down the file
+        if self != Self::Wraparound {
+            return Ok((0..cards.len()).collect());
         }
 
-        score
-    }
+        let mut winners = vec![0; cards.len()];
+        for target in targets.iter().flatten() {
+            winners[*target] += 1;
+        }
 
-    fn num_matchs(&self) -> i64 {
-        let mut num_matchs = 0;
-        for number in &self.my_numbers {
-            if self.winning_numbers.contains_key(number) {
-                num_matchs += 1;
+        let mut order: Vec<usize> = (0..cards.len()).filter(|x| winners[*x] == 0).collect();
+        let mut next = 0;
+        while next < order.len() {
+            for target in &targets[order[next]] {
+                winners[*target] -= 1;
+                if winners[*target] == 0 {
+                    order.push(*target);
+                }
             }
+            next += 1;
         }
 
-        num_matchs
+        match (0..cards.len()).find(|x| winners[*x] > 0) {
+            Some(index) => Err(CascadeError::Unbounded { card: cards[index].card_id }),
+            None => Ok(order),
+        }
     }
-    
 }
 
+// by the time a card is reached every copy of it has been won, so one pass
+// over the copy counts in order is enough
+fn cascade(cards: &[Card], targets: &[Vec<usize>], order: &[usize]) -> Result<u64, CascadeError> {
+    let mut copies: Vec<u64> = vec![1; cards.len()];
 
-pub fn question_one(in_file: BufReader<File>) -> Result<(), std::io::Error> {
-    let mut cards = Vec::new();
-    for line in in_file.lines() {
-        let validated_line = line?;
-
-        cards.push(Card::new(&validated_line));
+    for index in order {
+        for next in &targets[*index] {
+            copies[*next] = copies[*next].checked_add(copies[*index]).ok_or(CascadeError::Overflow { card: cards[*next].card_id })?;
+        }
     }
 
-    let score: i64 = cards.iter().map(|x| x.score()).sum();
+    copies.iter().try_fold(0u64, |acc, x| acc.checked_add(*x))
+        .ok_or(CascadeError::Overflow { card: cards.last().map(|x| x.card_id).unwrap_or(0) })
+}
 
-    println!("total: {}", score);
+fn cascade_big(targets: &[Vec<usize>], order: &[usize]) -> BigUint {
+    let mut copies: Vec<BigUint> = vec![BigUint::from(1u32); targets.len()];
 
-    Ok(())
+    for index in order {
+        for next in &targets[*index] {
+            let add = copies[*index].clone();
+            copies[*next] += add;
+        }
+    }
+
+    copies.iter().sum()
 }
 
-pub fn question_two(in_file: BufReader<File>) -> Result<(), std::io::Error> {
-    let mut card_map = HashMap::new();
-    let mut cards = Vec::new();
-    for line in in_file.lines() {
-        let validated_line = line?;
+fn read_lines(in_file: BufReader<File>) -> Result<Vec<String>, std::io::Error> {
+    in_file.lines().collect()
+}
 
-        let cc = Card::new(&validated_line);
-        card_map.insert(cc.card_id, (cc.clone(), 1));
-        cards.push(cc.clone());
+pub fn question_one(in_file: BufReader<File>, options: &Options) -> Result<(), std::io::Error> {
+    let lines = read_lines(in_file)?;
+    if let Some(iterations) = options.match_bench {
+        bench(&lines, iterations);
+        return Ok(());
     }
 
-    for card in cards.iter() {
-        let num_matches = card.num_matchs();
+    let cards: Vec<Card> = lines.iter().map(|x| Card::new(x)).collect();
 
-        let number_of_cards = card_map.get(&card.card_id).expect("failed to find known card count").1;
+    let scoring = Scoring::from_options(options)?;
 
-        for mm in 1..=num_matches {
-            if let Some(cc) = card_map.get_mut(&(card.card_id+mm)) {
-                cc.1 += number_of_cards;
-            };
-        }
+    let mut score: i64 = 0;
+    for card in &cards {
+        score = score.checked_add(scoring.points(card)?).ok_or(ScoreError::Overflow { card: card.card_id })?;
+    }
+
+    println!("total: {}", score);
+
+    Ok(())
+}
+
+pub fn question_two(in_file: BufReader<File>, options: &Options) -> Result<(), std::io::Error> {
+    let lines = read_lines(in_file)?;
+    if let Some(iterations) = options.match_bench {
+        bench(&lines, iterations);
+        return Ok(());
     }
 
-    let total_cards: i32 = card_map.values().map(|x| x.1).sum();
+    let cards: Vec<Card> = lines.iter().map(|x| Card::new(x)).collect();
 
-    println!("number of cards: {}", total_cards);
+    let targets = options.copies.targets(&cards);
+    let order = options.copies.order(&cards, &targets)?;
+
+    if options.big_counts {
+        println!("number of cards: {}", cascade_big(&targets, &order));
+    } else {
+        println!("number of cards: {}", cascade(&cards, &targets, &order)?);
+    }
 
     Ok(())
-}
\ No newline at end of file
+}
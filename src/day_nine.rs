@@ -1,6 +1,63 @@
-use std::{io::{BufReader, BufRead}, fs::File};
+use std::{io::{BufReader, BufRead}, fs::File, time::Instant, fmt::Display, error::Error};
 
-use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use clap::{Args, ValueEnum};
+use num_bigint::BigInt;
+use rayon::iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
+
+use crate::number_theory::Rational;
+
+#[derive(Args)]
+#[group(id = "day_nine")]
+pub struct Options {
+    /// Day nine: time the closed-form extrapolation against the difference table over this many runs
+    #[clap(long = "bench")]
+    bench: Option<u32>,
+    /// Day nine: extrapolate this many values (forwards for question one, backwards for question two) and print each extended sequence
+    #[clap(long = "extend")]
+    extend: Option<usize>,
+    /// Day nine: evaluate each fitted polynomial at this index, where 0 is the first value
+    #[clap(long = "at", allow_hyphen_values = true)]
+    at: Option<i128>,
+    /// Day nine: report the inferred polynomial degree of every sequence
+    #[clap(long = "degrees")]
+    degrees: bool,
+    /// Day nine: print the fitted polynomial of every sequence instead of predicting
+    #[clap(long = "formula", value_enum)]
+    formula: Option<FormulaFormat>,
+    /// Day nine: use arbitrary-precision integers from the start instead of only after an overflow
+    #[clap(long = "big")]
+    big: bool,
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+enum FormulaFormat {
+    Text,
+    Json,
+}
+
+#[derive(Debug)]
+enum SequenceError {
+    // never reaches an all-zero difference row, so it isn't a polynomial of degree < n - 1
+    NotPolynomial { line: usize },
+    Overflow { line: usize },
+}
+
+impl Display for SequenceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotPolynomial { line } => write!(f, "line {}: sequence is not a polynomial of degree less than its length - 1", line),
+            Self::Overflow { line } => write!(f, "line {}: prediction overflowed", line),
+        }
+    }
+}
+
+impl Error for SequenceError {}
+
+impl From<SequenceError> for std::io::Error {
+    fn from(value: SequenceError) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, value)
+    }
+}
 
 #[derive(PartialEq, Clone)]
 struct Sequence {
@@ -14,6 +71,20 @@ impl Sequence {
         Sequence { inner: seq }
     }
 
+    // values that are integers but don't fit an isize are an overflow, so the
+    // caller can fall back to a BigSequence
+    fn try_parse(line: &str, line_number: usize) -> Result<Sequence, SequenceError> {
+        let seq = line.split(' ').map(|x| match str::parse::<isize>(x) {
+            Ok(value) => Ok(value),
+            Err(_) => {
+                str::parse::<BigInt>(x).expect("boo not an integer");
+                Err(SequenceError::Overflow { line: line_number })
+            }
+        }).collect::<Result<Vec<isize>, _>>()?;
+
+        Ok(Sequence { inner: seq })
+    }
+
     fn next_sequence(&self) -> Sequence {
         let mut seq = Vec::new();
         for (idx, item) in self.inner.iter().enumerate() {
@@ -25,8 +96,43 @@ impl Sequence {
         Sequence { inner: seq }
     }
 
+    // the same walk down and back up the difference table as SequenceCollection,
+    // None if any difference or prediction doesn't fit an isize
+    fn checked_table_predict(&self, forwards: bool) -> Option<isize> {
+        let mut edges = Vec::new();
+        let mut row = self.inner.clone();
+
+        while !row.is_empty() && !row.iter().all(|x| *x == 0) {
+            edges.push(if forwards { *row.last()? } else { row[0] });
+            row = row.windows(2).map(|x| x[1].checked_sub(x[0])).collect::<Option<_>>()?;
+        }
+
+        edges.iter().rev().try_fold(0isize, |diff, edge| if forwards { edge.checked_add(diff) } else { edge.checked_sub(diff) })
+    }
+
     fn is_last_seq(&self) -> bool {
-        self.inner.iter().all(|x| *x == 0)
+        !self.inner.is_empty() && self.inner.iter().all(|x| *x == 0)
+    }
+
+    // the first all-zero difference row pins the degree. it has to have at
+    // least one value in it, otherwise nothing was actually verified
+    fn degree(&self, line: usize) -> Result<usize, SequenceError> {
+        let mut row: Vec<i128> = self.inner.iter().map(|x| *x as i128).collect();
+        let mut depth: usize = 0;
+
+        while !row.is_empty() {
+            if row.iter().all(|x| *x == 0) {
+                return Ok(depth.saturating_sub(1));
+            }
+
+            for idx in 0..row.len() - 1 {
+                row[idx] = row[idx + 1].checked_sub(row[idx]).ok_or(SequenceError::Overflow { line })?;
+            }
+            row.pop();
+            depth += 1;
+        }
+
+        Err(SequenceError::NotPolynomial { line })
     }
 
     fn predict(&self, next: isize) -> isize {
@@ -36,6 +142,206 @@ impl Sequence {
     fn predict_prev(&self, prev: isize) -> isize {
         self.inner.first().expect("no element") - prev
     }
+
+    // newton forward differences: the n-th difference of n + 1 points on a
+    // polynomial of degree < n is zero, so the point after the last is
+    // y_n = sum over k of (-1)^(n-1-k) * C(n, k) * y_k
+    fn extrapolate_next(&self) -> Option<isize> {
+        let n = self.inner.len();
+        let mut total: i128 = 0;
+
+        for (k, (binomial, y)) in binomial_row(n)?.into_iter().zip(&self.inner).enumerate() {
+            let term = binomial.checked_mul(*y as i128)?;
+            total = if (n - 1 - k).is_multiple_of(2) { total.checked_add(term)? } else { total.checked_sub(term)? };
+        }
+
+        isize::try_from(total).ok()
+    }
+
+    // same identity shifted one to the left: y_-1 = sum over k of (-1)^k * C(n, k + 1) * y_k
+    fn extrapolate_prev(&self) -> Option<isize> {
+        let n = self.inner.len();
+        let mut total: i128 = 0;
+
+        for (k, (binomial, y)) in binomial_row(n)?.into_iter().skip(1).zip(&self.inner).enumerate() {
+            let term = binomial.checked_mul(*y as i128)?;
+            total = if k.is_multiple_of(2) { total.checked_add(term)? } else { total.checked_sub(term)? };
+        }
+
+        isize::try_from(total).ok()
+    }
+}
+
+// the polynomial through a sequence, in newton form: p(x) = sum over j of C(x, j) * d_j
+// where d_j is the first value of the j-th difference row
+struct NewtonSeries {
+    differences: Vec<i128>,
+}
+
+impl NewtonSeries {
+    fn fit(sequence: &Sequence, line: usize) -> Result<NewtonSeries, SequenceError> {
+        let degree = sequence.degree(line)?;
+        let mut row: Vec<i128> = sequence.inner.iter().map(|x| *x as i128).collect();
+        let mut differences = Vec::with_capacity(degree + 1);
+
+        for _ in 0..=degree {
+            differences.push(row[0]);
+            for idx in 0..row.len() - 1 {
+                row[idx] = row[idx + 1].checked_sub(row[idx]).ok_or(SequenceError::Overflow { line })?;
+            }
+            row.pop();
+        }
+
+        Ok(NewtonSeries { differences })
+    }
+
+    // C(x, j) for negative x is still an integer, and c * (x - j) is always
+    // divisible by j + 1, so this stays exact
+    fn at(&self, x: i128) -> Option<i128> {
+        let mut total: i128 = 0;
+        let mut binomial: i128 = 1;
+
+        for (j, difference) in self.differences.iter().enumerate() {
+            total = total.checked_add(binomial.checked_mul(*difference)?)?;
+            binomial = binomial.checked_mul(x.checked_sub(j as i128)?)? / (j as i128 + 1);
+        }
+
+        Some(total)
+    }
+}
+
+// the same sequence stored in arbitrary-precision integers, for inputs where
+// the values or their differences don't fit in fixed width
+struct BigSequence {
+    inner: Vec<BigInt>,
+}
+
+impl BigSequence {
+    fn parse(line: &str) -> BigSequence {
+        BigSequence { inner: line.split(' ').map(|x| str::parse(x).expect("boo not an integer")).collect() }
+    }
+
+    // first value of every difference row above the first all-zero one
+    fn leading_differences(&self, line: usize) -> Result<Vec<BigInt>, SequenceError> {
+        let mut row = self.inner.clone();
+        let mut differences = Vec::with_capacity(row.len());
+
+        while !row.is_empty() {
+            if row.iter().all(|x| *x == BigInt::ZERO) {
+                if differences.is_empty() {
+                    differences.push(BigInt::ZERO);
+                }
+                return Ok(differences);
+            }
+
+            differences.push(row[0].clone());
+            for idx in 0..row.len() - 1 {
+                row[idx] = &row[idx + 1] - &row[idx];
+            }
+            row.pop();
+        }
+
+        Err(SequenceError::NotPolynomial { line })
+    }
+}
+
+struct BigNewtonSeries {
+    differences: Vec<BigInt>,
+}
+
+impl BigNewtonSeries {
+    fn at(&self, x: i128) -> BigInt {
+        let mut total = BigInt::ZERO;
+        let mut binomial = BigInt::from(1);
+
+        for (j, difference) in self.differences.iter().enumerate() {
+            total += &binomial * difference;
+            binomial = binomial * (BigInt::from(x) - j) / (j + 1);
+        }
+
+        total
+    }
+}
+
+// what the prediction and forecasting code needs from a sequence, whichever
+// integers it is stored in. values come back as BigInt so both can be summed
+trait Extrapolate: Sync {
+    fn describe(&self) -> String;
+    fn value_count(&self) -> usize;
+    fn degree(&self, line: usize) -> Result<usize, SequenceError>;
+    fn next(&self, line: usize) -> Result<BigInt, SequenceError>;
+    fn prev(&self, line: usize) -> Result<BigInt, SequenceError>;
+    fn evaluator(&self, line: usize) -> Result<Box<dyn Fn(i128) -> Option<BigInt> + '_>, SequenceError>;
+}
+
+impl Extrapolate for Sequence {
+    fn describe(&self) -> String {
+        self.inner.iter().map(|x| x.to_string()).collect::<Vec<_>>().join(" ")
+    }
+
+    fn value_count(&self) -> usize {
+        self.inner.len()
+    }
+
+    fn degree(&self, line: usize) -> Result<usize, SequenceError> {
+        Sequence::degree(self, line)
+    }
+
+    fn next(&self, line: usize) -> Result<BigInt, SequenceError> {
+        self.extrapolate_next().map(BigInt::from).ok_or(SequenceError::Overflow { line })
+    }
+
+    fn prev(&self, line: usize) -> Result<BigInt, SequenceError> {
+        self.extrapolate_prev().map(BigInt::from).ok_or(SequenceError::Overflow { line })
+    }
+
+    fn evaluator(&self, line: usize) -> Result<Box<dyn Fn(i128) -> Option<BigInt> + '_>, SequenceError> {
+        let series = NewtonSeries::fit(self, line)?;
+        Ok(Box::new(move |x| series.at(x).map(BigInt::from)))
+    }
+}
+
+impl Extrapolate for BigSequence {
+    fn describe(&self) -> String {
+        self.inner.iter().map(|x| x.to_string()).collect::<Vec<_>>().join(" ")
+    }
+
+    fn value_count(&self) -> usize {
+        self.inner.len()
+    }
+
+    fn degree(&self, line: usize) -> Result<usize, SequenceError> {
+        Ok(self.leading_differences(line)?.len() - 1)
+    }
+
+    fn next(&self, line: usize) -> Result<BigInt, SequenceError> {
+        let series = BigNewtonSeries { differences: self.leading_differences(line)? };
+        Ok(series.at(self.inner.len() as i128))
+    }
+
+    fn prev(&self, line: usize) -> Result<BigInt, SequenceError> {
+        let series = BigNewtonSeries { differences: self.leading_differences(line)? };
+        Ok(series.at(-1))
+    }
+
+    fn evaluator(&self, line: usize) -> Result<Box<dyn Fn(i128) -> Option<BigInt> + '_>, SequenceError> {
+        let series = BigNewtonSeries { differences: self.leading_differences(line)? };
+        Ok(Box::new(move |x| Some(series.at(x))))
+    }
+}
+
+// C(n, 0) ..= C(n, n), None if a coefficient doesn't fit
+fn binomial_row(n: usize) -> Option<Vec<i128>> {
+    let mut row = Vec::with_capacity(n + 1);
+    let mut current: i128 = 1;
+    row.push(current);
+
+    for k in 0..n {
+        current = current.checked_mul((n - k) as i128)? / (k + 1) as i128;
+        row.push(current);
+    }
+
+    Some(row)
 }
 
 struct SequenceCollection {
@@ -45,7 +351,10 @@ struct SequenceCollection {
 
 impl SequenceCollection {
     fn parse(line: String) -> SequenceCollection {
-        let seq = Sequence::parse(line);
+        SequenceCollection::new(Sequence::parse(line))
+    }
+
+    fn new(seq: Sequence) -> SequenceCollection {
         SequenceCollection { starting_collection: seq.clone(), collection: vec![seq] }
     }
 
@@ -53,6 +362,7 @@ impl SequenceCollection {
         let mut starting_seq = self.starting_collection.clone();
 
         while !starting_seq.is_last_seq() {
+            assert!(starting_seq.inner.len() > 1, "sequence is not a polynomial");
             starting_seq = starting_seq.next_sequence();
             self.collection.push(starting_seq.clone());
         }
@@ -87,25 +397,281 @@ impl SequenceCollection {
     }
 }
 
+// exact coefficients in ascending powers of x, where x = 0 is the first value
+struct Polynomial {
+    coefficients: Vec<Rational>,
+}
+
+impl Polynomial {
+    // expands sum over j of d_j * C(x, j), each C(x, j) being the falling
+    // factorial x (x - 1) ... (x - j + 1) over j!
+    fn from_differences(differences: &[i128], line: usize) -> Result<Polynomial, SequenceError> {
+        let mut coefficients = vec![Rational::integer(0); differences.len().max(1)];
+        let mut falling: Vec<i128> = vec![1];
+        let mut factorial: i128 = 1;
+
+        for (j, difference) in differences.iter().enumerate() {
+            if j > 0 {
+                factorial = factorial.checked_mul(j as i128).ok_or(SequenceError::Overflow { line })?;
+
+                // multiply the falling factorial by (x - (j - 1))
+                let mut next: Vec<i128> = vec![0; falling.len() + 1];
+                for (power, coefficient) in falling.iter().enumerate() {
+                    next[power + 1] = next[power + 1].checked_add(*coefficient).ok_or(SequenceError::Overflow { line })?;
+                    let shifted = coefficient.checked_mul(j as i128 - 1).and_then(|x| next[power].checked_sub(x));
+                    next[power] = shifted.ok_or(SequenceError::Overflow { line })?;
+                }
+                falling = next;
+            }
+
+            for (power, coefficient) in falling.iter().enumerate() {
+                let numerator = coefficient.checked_mul(*difference).ok_or(SequenceError::Overflow { line })?;
+                let term = Rational::new(numerator, factorial).ok_or(SequenceError::Overflow { line })?;
+                coefficients[power] = coefficients[power].checked_add(term).ok_or(SequenceError::Overflow { line })?;
+            }
+        }
+
+        // display flips negative coefficients, so make sure that can't fail later
+        if coefficients.iter().any(|x| x.checked_neg().is_none()) {
+            return Err(SequenceError::Overflow { line });
+        }
+
+        Ok(Polynomial { coefficients })
+    }
+
+    fn to_json(&self, line: usize) -> String {
+        let coefficients = self.coefficients.iter()
+            .map(|x| format!("{{\"numerator\": {}, \"denominator\": {}}}", x.numerator(), x.denominator()))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        format!("{{\"line\": {}, \"degree\": {}, \"formula\": \"{}\", \"coefficients\": [{}]}}", line, self.coefficients.len() - 1, self, coefficients)
+    }
+}
+
+impl Display for Polynomial {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "p(x) =")?;
+
+        let mut first = true;
+        for (power, coefficient) in self.coefficients.iter().enumerate().rev() {
+            if coefficient.is_zero() {
+                continue;
+            }
+
+            let negative = coefficient.numerator() < 0;
+            let magnitude = if negative { coefficient.checked_neg().expect("checked when built") } else { *coefficient };
+
+            let sign = match (first, negative) {
+                (true, true) => " -",
+                (true, false) => " ",
+                (false, true) => " - ",
+                (false, false) => " + ",
+            };
+            write!(f, "{}", sign)?;
+            first = false;
+
+            let is_one = magnitude == Rational::integer(1);
+            match power {
+                0 => write!(f, "{}", magnitude)?,
+                1 if is_one => write!(f, "x")?,
+                1 => write!(f, "{}x", magnitude)?,
+                _ if is_one => write!(f, "x^{}", power)?,
+                _ => write!(f, "{}x^{}", magnitude, power)?,
+            }
+        }
+
+        if first {
+            write!(f, " 0")?;
+        }
+
+        Ok(())
+    }
+}
+
+fn print_formulas(sequences: &[Sequence], format: FormulaFormat) -> Result<(), SequenceError> {
+    let mut json = Vec::new();
+
+    for (idx, sequence) in sequences.iter().enumerate() {
+        let series = NewtonSeries::fit(sequence, idx + 1)?;
+        let polynomial = Polynomial::from_differences(&series.differences, idx + 1)?;
+
+        match format {
+            FormulaFormat::Text => println!("line {}: {}", idx + 1, polynomial),
+            FormulaFormat::Json => json.push(polynomial.to_json(idx + 1)),
+        }
+    }
+
+    if let FormulaFormat::Json = format {
+        println!("[{}]", json.join(",\n "));
+    }
+
+    Ok(())
+}
+
+// lines are 1-indexed in errors
+fn parse_sequences(lines: &[String]) -> Result<Vec<Sequence>, SequenceError> {
+    lines.iter().enumerate().map(|(idx, x)| Sequence::try_parse(x, idx + 1)).collect()
+}
+
+fn validate<S: Extrapolate>(sequences: &[S]) -> Result<(), SequenceError> {
+    for (idx, sequence) in sequences.iter().enumerate() {
+        sequence.degree(idx + 1)?;
+    }
+
+    Ok(())
+}
+
+// the difference table and the closed form both work in isize, so every
+// sequence has to predict without overflowing either way before timing them
+fn check_bench(sequences: &[Sequence], forwards: bool) -> Result<(), SequenceError> {
+    let mut total: isize = 0;
 
-pub fn question_one(in_file: BufReader<File>) -> Result<(), std::io::Error> {
-    let lines: Vec<String> = in_file.lines().map(|x| x.expect("boo! bad string")).collect();
-    let mut collections: Vec<SequenceCollection> = lines.into_iter().map(SequenceCollection::parse).collect();
-    collections = collections.into_iter().map(|mut x| { x.fill(); x }).collect();
-    let result: isize = collections.par_iter().map(|x| x.predict()).sum();
+    for (idx, sequence) in sequences.iter().enumerate() {
+        let line = idx + 1;
+        let closed = if forwards { sequence.extrapolate_next() } else { sequence.extrapolate_prev() };
+        let value = closed.and(sequence.checked_table_predict(forwards)).ok_or(SequenceError::Overflow { line })?;
 
-    println!("result: {}", result);
+        total = total.checked_add(value).ok_or(SequenceError::Overflow { line })?;
+    }
 
     Ok(())
 }
 
-pub fn question_two(in_file: BufReader<File>) -> Result<(), std::io::Error> {
-    let lines: Vec<String> = in_file.lines().map(|x| x.expect("boo! bad string")).collect();
-    let mut collections: Vec<SequenceCollection> = lines.into_iter().map(SequenceCollection::parse).collect();
-    collections = collections.into_iter().map(|mut x| { x.fill(); x }).collect();
-    let result: isize = collections.par_iter().map(|x| x.predict_prev()).sum();
+fn bench(sequences: &[Sequence], iterations: u32, forwards: bool) {
+    let lines: Vec<String> = sequences.iter().map(|x| x.describe()).collect();
+
+    let start = Instant::now();
+    let mut table_result = 0;
+    for _ in 0..iterations {
+        let mut collections: Vec<SequenceCollection> = lines.iter().cloned().map(SequenceCollection::parse).collect();
+        collections = collections.into_iter().map(|mut x| { x.fill(); x }).collect();
+        table_result = collections.iter().map(|x| if forwards { x.predict() } else { x.predict_prev() }).sum::<isize>();
+    }
+    let table_time = start.elapsed();
+
+    let start = Instant::now();
+    let mut closed_result = 0;
+    for _ in 0..iterations {
+        let sequences: Vec<Sequence> = lines.iter().cloned().map(Sequence::parse).collect();
+        closed_result = sequences.iter().map(|x| if forwards { x.extrapolate_next() } else { x.extrapolate_prev() }.expect("prediction overflowed")).sum::<isize>();
+    }
+    let closed_time = start.elapsed();
+
+    println!("difference table: {} in {:?} ({:?} per run)", table_result, table_time, table_time / iterations);
+    println!("closed form: {} in {:?} ({:?} per run)", closed_result, closed_time, closed_time / iterations);
+}
+
+fn forecast<S: Extrapolate>(sequences: &[S], options: &Options, forwards: bool) -> Result<Vec<String>, SequenceError> {
+    let mut output = Vec::new();
+    let mut result = BigInt::ZERO;
+
+    for (idx, sequence) in sequences.iter().enumerate() {
+        let line = idx + 1;
+        let series = sequence.evaluator(line)?;
+        let evaluate = |index: i128| series(index).ok_or(SequenceError::Overflow { line });
+
+        if let Some(index) = options.at {
+            let value = evaluate(index)?;
+            output.push(format!("{} @ {}: {}", sequence.describe(), index, value));
+            result += value;
+            continue;
+        }
 
-    println!("result: {}", result);
+        let steps = options.extend.unwrap_or(1) as i128;
+        let len = sequence.value_count() as i128;
+        let original = sequence.describe();
+
+        let (predicted, line) = if forwards {
+            let mut predicted = (len..len + steps).map(evaluate).collect::<Result<Vec<_>, _>>()?;
+            let line = format!("[{}] {}", original, predicted.iter().map(|x| x.to_string()).collect::<Vec<_>>().join(" "));
+            (predicted.pop(), line)
+        } else {
+            let predicted = (-steps..0).map(evaluate).collect::<Result<Vec<_>, _>>()?;
+            let line = format!("{} [{}]", predicted.iter().map(|x| x.to_string()).collect::<Vec<_>>().join(" "), original);
+            (predicted.into_iter().next(), line)
+        };
+
+        output.push(line);
+        result += predicted.unwrap_or_default();
+    }
+
+    output.push(format!("result: {}", result));
+
+    Ok(output)
+}
+
+// everything is buffered so an overflow part way through can be retried
+// with big integers without having printed half an answer
+fn run<S: Extrapolate>(sequences: &[S], options: &Options, forwards: bool) -> Result<Vec<String>, SequenceError> {
+    validate(sequences)?;
+
+    let mut output = Vec::new();
+
+    if options.degrees {
+        for (idx, sequence) in sequences.iter().enumerate() {
+            output.push(format!("line {}: degree {}", idx + 1, sequence.degree(idx + 1)?));
+        }
+    }
+
+    if options.extend.is_some() || options.at.is_some() {
+        output.append(&mut forecast(sequences, options, forwards)?);
+        return Ok(output);
+    }
+
+    let predictions: Vec<BigInt> = sequences.par_iter().enumerate().map(|(idx, x)| {
+        if forwards { x.next(idx + 1) } else { x.prev(idx + 1) }
+    }).collect::<Result<_, _>>()?;
+    let result: BigInt = predictions.into_iter().sum();
+
+    output.push(format!("result: {}", result));
+
+    Ok(output)
+}
+
+fn solve(in_file: BufReader<File>, options: &Options, forwards: bool) -> Result<(), std::io::Error> {
+    let lines: Vec<String> = in_file.lines().collect::<Result<_, _>>()?;
+
+    // the formula and the benchmark only work on fixed width sequences
+    if options.formula.is_some() || options.bench.is_some() {
+        let sequences = parse_sequences(&lines)?;
+        validate(&sequences)?;
+
+        if let Some(format) = options.formula {
+            print_formulas(&sequences, format)?;
+        } else if let Some(iterations) = options.bench {
+            check_bench(&sequences, forwards)?;
+            bench(&sequences, iterations, forwards);
+        }
+
+        return Ok(());
+    }
+
+    let big_sequences = || lines.iter().map(|x| BigSequence::parse(x)).collect::<Vec<_>>();
+
+    let output = if options.big {
+        run(&big_sequences(), options, forwards)?
+    } else {
+        match parse_sequences(&lines).and_then(|x| run(&x, options, forwards)) {
+            Err(SequenceError::Overflow { line }) => {
+                eprintln!("line {}: overflowed, retrying with big integers", line);
+                run(&big_sequences(), options, forwards)?
+            }
+            result => result?,
+        }
+    };
+
+    for line in output {
+        println!("{}", line);
+    }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+pub fn question_one(in_file: BufReader<File>, options: &Options) -> Result<(), std::io::Error> {
+    solve(in_file, options, true)
+}
+
+pub fn question_two(in_file: BufReader<File>, options: &Options) -> Result<(), std::io::Error> {
+    solve(in_file, options, false)
+}
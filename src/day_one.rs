@@ -1,97 +1,206 @@
-use std::{io::{BufReader, BufRead}, fs::File, collections::HashMap};
-use regex::Regex;
-use regex::RegexSet;
+use std::{io::{BufReader, BufRead, IsTerminal}, fs::File, fmt::Display, error::Error};
 
-pub fn question_one(in_file: BufReader<File>) -> Result<(), std::io::Error> {
+use clap::{Args, ValueEnum};
 
-    let long_regex = Regex::new(r"(\d).*(\d)").unwrap();
-    let short_regex = Regex::new(r".*(\d)").unwrap();
+use crate::config;
 
-    let regex_set = RegexSet::new([
-        r"(\d).*(\d)",
-        r".*(\d)"
-    ]).unwrap();
-    let mut total = 0;
+#[derive(Args)]
+#[group(id = "day_one")]
+pub struct Options {
+    /// Day one: print the first and last digit tokens picked on every line
+    #[clap(long = "tokens")]
+    tokens: bool,
+    /// Day one: trace every line with the tokens picked, their byte offsets, the line value and the running total
+    #[clap(long = "explain", conflicts_with = "tokens")]
+    explain: bool,
+    /// Day one: language of the spelled-out digits for question two
+    #[clap(long = "vocabulary", value_enum, default_value_t = Language::English)]
+    vocabulary: Language,
+    /// Day one: read the spelled-out digits for question two from a file of "word value" lines instead
+    #[clap(long = "words", conflicts_with = "vocabulary")]
+    words: Option<String>,
+    /// Day one: also accept the spelled-out word for zero
+    #[clap(long = "zero")]
+    zero: bool,
+}
 
-    for line in in_file.lines() {
-        let verified_line = line?;
-        let matches = regex_set.matches(&verified_line);
-        if matches.matched(0) {
-            for (_, [first, last]) in long_regex.captures_iter(&verified_line).map(|c| c.extract()) {
-                let fmt_str = format!("{}{}", first, last);
-                let sum = str::parse::<i32>(&fmt_str).unwrap();
-                total += sum;
-            }
-        } else if matches.matched(1) {
-            for (_, [first]) in short_regex.captures_iter(&verified_line).map(|c| c.extract()) {
-                let fmt_str = format!("{}{}", first, first);
-                let sum = str::parse::<i32>(&fmt_str).unwrap();
-                total += sum;
-            }
+#[derive(ValueEnum, Clone, Copy)]
+enum Language {
+    English,
+    German,
+    Spanish,
+    French,
+}
+
+impl Language {
+    // indexed by the digit each word stands for
+    fn words(self) -> [&'static str; 10] {
+        match self {
+            Self::English => ["zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine"],
+            Self::German => ["null", "eins", "zwei", "drei", "vier", "fünf", "sechs", "sieben", "acht", "neun"],
+            Self::Spanish => ["cero", "uno", "dos", "tres", "cuatro", "cinco", "seis", "siete", "ocho", "nueve"],
+            Self::French => ["zéro", "un", "deux", "trois", "quatre", "cinq", "six", "sept", "huit", "neuf"],
         }
     }
-    println!("total is: {}", total);
+}
 
-    Ok(())
+#[derive(Debug)]
+enum VocabularyError {
+    Malformed { line: usize, text: String },
+    NotADigit { line: usize, value: String },
 }
 
-pub fn question_two(in_file: BufReader<File>) -> Result<(), std::io::Error> {
-    let long_regex = Regex::new(r"(one|two|three|four|five|six|seven|eight|nine|\d).*(one|two|three|four|five|six|seven|eight|nine|\d)").unwrap();
-    let short_regex = Regex::new(r".*(one|two|three|four|five|six|seven|eight|nine|\d)").unwrap();
-    let regex_set = RegexSet::new([
-        r"(one|two|three|four|five|six|seven|eight|nine|\d).*(one|two|three|four|five|six|seven|eight|nine|\d)",
-        r".*(one|two|three|four|five|six|seven|eight|nine|\d)"
-    ]).unwrap();
-
-    let mut mapping_hm = HashMap::new();
-    mapping_hm.insert("one", "1");
-    mapping_hm.insert("two", "2");
-    mapping_hm.insert("three", "3");
-    mapping_hm.insert("four", "4");
-    mapping_hm.insert("five", "5");
-    mapping_hm.insert("six", "6");
-    mapping_hm.insert("seven", "7");
-    mapping_hm.insert("eight", "8");
-    mapping_hm.insert("nine", "9");
+impl Display for VocabularyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Malformed { line, text } => write!(f, "line {}: expected \"word value\", found \"{}\"", line, text),
+            Self::NotADigit { line, value } => write!(f, "line {}: {} is not a digit from 0 to 9", line, value),
+        }
+    }
+}
 
+impl Error for VocabularyError {}
 
-    let mut total = 0;
+impl From<VocabularyError> for std::io::Error {
+    fn from(value: VocabularyError) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, value)
+    }
+}
 
-    for line in in_file.lines() {
-        let verified_line = line?;
-        let matches = regex_set.matches(&verified_line);
-        if matches.matched(0) {
-            for (_, [first, last]) in long_regex.captures_iter(&verified_line).map(|c| c.extract()) {
-                let fixed_first = if let Some(mapped_first) = mapping_hm.get(first) {
-                    mapped_first
-                } else {
-                    first
-                };
-                let fixed_last = if let Some(mapped_last) = mapping_hm.get(last) {
-                    mapped_last
-                } else {
-                    last
+#[derive(Clone, Copy)]
+struct DigitToken<'a> {
+    text: &'a str,
+    offset: usize,
+    value: u32,
+}
+
+// finds digit tokens at every byte offset, so spelled-out words that share
+// letters (twone, eightwo) are both seen instead of the first one eating the
+// second like a regex match would
+struct Scanner {
+    words: Vec<(String, u32)>,
+}
+
+impl Scanner {
+    fn digits_only() -> Scanner {
+        Scanner { words: Vec::new() }
+    }
+
+    fn spelled(language: Language, zero: bool) -> Scanner {
+        let words = language.words().iter()
+            .enumerate()
+            .map(|(value, word)| (word.to_string(), value as u32))
+            .collect();
+
+        Scanner::from_words(words, zero)
+    }
+
+    fn from_word_list(in_file: BufReader<File>, zero: bool) -> Result<Scanner, std::io::Error> {
+        let mut words = Vec::new();
+
+        for entry in config::entries(in_file) {
+            let (line, text) = entry?;
+            let (word, value) = match text.split_whitespace().collect::<Vec<_>>()[..] {
+                [word, value] => (word, value),
+                _ => return Err(VocabularyError::Malformed { line, text }.into()),
+            };
+            let digit = value.parse::<u32>().ok()
+                .filter(|x| *x <= 9)
+                .ok_or_else(|| VocabularyError::NotADigit { line, value: value.to_string() })?;
+
+            words.push((word.to_string(), digit));
+        }
+
+        Ok(Scanner::from_words(words, zero))
+    }
+
+    fn from_words(mut words: Vec<(String, u32)>, zero: bool) -> Scanner {
+        if !zero {
+            words.retain(|(_, value)| *value != 0);
+        }
+        // longest first, so a word that is a prefix of another can't shadow it
+        words.sort_by_key(|(word, _)| std::cmp::Reverse(word.len()));
+
+        Scanner { words }
+    }
+
+    fn token_at<'a>(&self, line: &'a str, offset: usize) -> Option<DigitToken<'a>> {
+        let rest = &line[offset..];
+        let first = rest.chars().next()?;
+
+        if let Some(value) = first.to_digit(10) {
+            return Some(DigitToken { text: &rest[..1], offset, value });
+        }
+
+        self.words.iter()
+            .find(|(word, _)| rest.starts_with(word.as_str()))
+            .map(|(word, value)| DigitToken { text: &rest[..word.len()], offset, value: *value })
+    }
+
+    // single forward pass, keeping the first token seen and the latest one
+    fn first_and_last<'a>(&self, line: &'a str) -> Option<(DigitToken<'a>, DigitToken<'a>)> {
+        let mut found: Option<(DigitToken, DigitToken)> = None;
+
+        for (offset, _) in line.char_indices() {
+            if let Some(token) = self.token_at(line, offset) {
+                found = match found {
+                    Some((first, _)) => Some((first, token)),
+                    None => Some((token, token)),
                 };
-                let fmt_str = format!("{}{}", fixed_first, fixed_last);
-                println!("{}", fmt_str);
-                let sum = str::parse::<i32>(&fmt_str).unwrap();
-                total += sum;
             }
-        } else if matches.matched(1) {
-            for (_, [first]) in short_regex.captures_iter(&verified_line).map(|c| c.extract()) {
-                let fixed_first = if let Some(mapped_first) = mapping_hm.get(first) {
-                    mapped_first
+        }
+
+        found
+    }
+}
+
+fn calibrate(in_file: BufReader<File>, scanner: &Scanner, options: &Options) -> Result<(), std::io::Error> {
+    let colour = options.explain && std::io::stdout().is_terminal();
+    let mut total = 0;
+
+    for (index, line) in in_file.lines().enumerate() {
+        let verified_line = line?;
+
+        match scanner.first_and_last(&verified_line) {
+            Some((first, last)) => {
+                let value = first.value * 10 + last.value;
+                total += value;
+                if options.tokens {
+                    println!("{} -> {} {} = {}", verified_line, first.text, last.text, value);
+                }
+                if options.explain {
+                    println!(
+                        "line {}: first \"{}\" at {}, last \"{}\" at {} -> {}, running total {}",
+                        index + 1, first.text, first.offset, last.text, last.offset, value, total
+                    );
+                }
+            },
+            None if options.explain => {
+                // these add nothing to the total, which is easy to miss
+                let warning = format!("line {}: no digits in \"{}\", skipped", index + 1, verified_line);
+                if colour {
+                    println!("\x1b[1;31m{}\x1b[0m", warning);
                 } else {
-                    first
-                };
-                let fmt_str = format!("{}{}", fixed_first, fixed_first);
-                let sum = str::parse::<i32>(&fmt_str).unwrap();
-                total += sum;
-            }
+                    println!("{} <--", warning);
+                }
+            },
+            None => (),
         }
     }
     println!("total is: {}", total);
 
-
     Ok(())
-}
\ No newline at end of file
+}
+
+pub fn question_one(in_file: BufReader<File>, options: &Options) -> Result<(), std::io::Error> {
+    calibrate(in_file, &Scanner::digits_only(), options)
+}
+
+pub fn question_two(in_file: BufReader<File>, options: &Options) -> Result<(), std::io::Error> {
+    let scanner = match &options.words {
+        Some(path) => Scanner::from_word_list(BufReader::new(File::open(path)?), options.zero)?,
+        None => Scanner::spelled(options.vocabulary, options.zero),
+    };
+
+    calibrate(in_file, &scanner, options)
+}
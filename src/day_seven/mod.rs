@@ -4,7 +4,7 @@ mod question_two;
 pub use self::question_one::question_one;
 pub use self::question_two::question_two;
 
-use std::{io::{BufReader, BufRead}, fs::File, cmp::Ordering, fmt::Debug};
+use std::fmt::Debug;
 
 #[derive(PartialEq, PartialOrd, Eq, Ord)]
 enum HandType {
@@ -77,21 +77,7 @@ struct Hand {
 
 impl PartialOrd for Hand {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        match self.ty.partial_cmp(&other.ty) {
-            Some(Ordering::Equal) => {
-                for (position, my_card) in self.cards.iter().enumerate() {
-                    let other_card = &other.cards[position];
-                    match my_card.partial_cmp(other_card) {
-                        Some(Ordering::Equal) => {
-                            continue;
-                        },
-                        ord => return ord,
-                    }
-                }
-                None
-            }
-            ord => ord,
-        }
+        Some(self.cmp(other))
     }
 }
 
@@ -103,9 +89,23 @@ impl Debug for Hand {
 
 impl Ord for Hand {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.partial_cmp(other).expect("this should never happen")
+        match self.ty.cmp(&other.ty) {
+            Ordering::Equal => {
+                for (position, my_card) in self.cards.iter().enumerate() {
+                    let other_card = &other.cards[position];
+                    match my_card.cmp(other_card) {
+                        Ordering::Equal => {
+                            continue;
+                        },
+                        ord => return ord,
+                    }
+                }
+                Ordering::Equal
+            }
+            ord => ord,
+        }
     }
-} 
+}
 
 fn compute_hand_type(cards: &[Card]) -> HandType {
 
@@ -123,8 +123,7 @@ fn compute_hand_type(cards: &[Card]) -> HandType {
     };
 
     let is_four_kind = {
-        (card_check[0] == card_check[1] && card_check[1] == card_check[2] && card_check[2] == card_check[3])
-        || (card_check[1] == card_check[2] && card_check[2] == card_check[3] && card_check[3] == card_check[4])
+        card_check[1] == card_check[2] && card_check[2] == card_check[3] && (card_check[0] == card_check[1] || card_check[3] == card_check[4])
     };
 
     if is_four_kind {
@@ -133,8 +132,7 @@ fn compute_hand_type(cards: &[Card]) -> HandType {
     };
 
     let is_full_house = {
-        ((card_check[0] == card_check[1] && card_check[1] == card_check[2]) && (card_check[3] == card_check[4]))
-        || ((card_check[2] == card_check[3] && card_check[3] == card_check[4]) && (card_check[0] == card_check[1]))
+        card_check[0] == card_check[1] && card_check[3] == card_check[4] && (card_check[1] == card_check[2] || card_check[2] == card_check[3])
     };
 
     if is_full_house {
@@ -143,8 +141,7 @@ fn compute_hand_type(cards: &[Card]) -> HandType {
     };
 
     let is_three_kind = {
-        (card_check[0] == card_check[1] && card_check[1] == card_check[2])
-        || (card_check[1] == card_check[2] && card_check[2] == card_check[3])
+        (card_check[1] == card_check[2] && (card_check[0] == card_check[1] || card_check[2] == card_check[3]))
         || (card_check[2] == card_check[3] && card_check[3] == card_check[4])
     };
 
@@ -1,14 +1,138 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error;
+use std::fmt::Display;
 use std::ops::Index;
 use std::{io::BufReader, fs::File};
-use std::io::BufRead;
+use std::io::{BufRead, IsTerminal};
+
+use clap::{Args, ValueEnum};
+
+use crate::grid::{Coordinate, Grid, GridError};
+
+#[derive(Args)]
+#[group(id = "day_three")]
+pub struct Options {
+    /// Day three: print the schematic with part numbers, symbols and gears highlighted
+    #[clap(long = "render")]
+    render: bool,
+    /// Day three: characters that count as symbols, defaults to anything that isn't a digit or blank
+    #[clap(long = "symbols")]
+    symbols: Option<String>,
+    /// Day three: character for an empty cell
+    #[clap(long = "blank", default_value_t = '.')]
+    blank: char,
+    /// Day three: character for a gear
+    #[clap(long = "gear", default_value_t = '*')]
+    gear: char,
+    /// Day three: exact number of adjacent part numbers a gear needs
+    #[clap(long = "gear-parts", default_value_t = 2)]
+    gear_parts: usize,
+    /// Day three: how a gear's adjacent part numbers combine into its ratio
+    #[clap(long = "gear-combine", value_enum, default_value_t = GearCombine::Product)]
+    gear_combine: GearCombine,
+    /// Day three: evaluate with a sliding three-row window instead of loading the whole schematic
+    #[clap(long = "stream", conflicts_with = "render")]
+    stream: bool,
+    /// Day three: accept rows of different lengths, treating missing cells as blank
+    #[clap(long = "ragged")]
+    ragged: bool,
+    /// Day three: print adjacency statistics for the schematic instead of the total
+    #[clap(long = "stats", value_enum, conflicts_with_all = ["stream", "render"])]
+    stats: Option<StatsFormat>,
+}
 
-struct EngineSchematic {
-    rows: Vec<Row>
+#[derive(ValueEnum, Clone, Copy)]
+enum StatsFormat {
+    Table,
+    Json,
+}
+
+// lines and columns are 1-indexed, as they'd show in an editor
+#[derive(Debug)]
+enum SchematicError {
+    Ragged { line: usize, expected: usize, found: usize },
+    NonAscii { line: usize, column: usize, found: char },
+    NumberOverflow { line: usize, column: usize },
+    RatioOverflow { line: usize, column: usize },
+    StreamingGear(char),
+}
+
+impl Display for SchematicError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Ragged { line, expected, found } => write!(f, "line {}: row has {} columns but the schematic is {} wide, pass --ragged to pad short rows", line, found, expected),
+            Self::NonAscii { line, column, found } => write!(f, "line {}, column {}: non-ascii character {:?} in schematic", line, column, found),
+            Self::NumberOverflow { line, column } => write!(f, "line {}, column {}: number is too large", line, column),
+            Self::RatioOverflow { line, column } => write!(f, "line {}, column {}: gear ratio is too large", line, column),
+            Self::StreamingGear(gear) => write!(f, "streaming needs an ascii gear character that counts as a symbol, got {:?}", gear),
+        }
+    }
+}
+
+impl Error for SchematicError {}
+
+impl From<GridError> for SchematicError {
+    fn from(value: GridError) -> Self {
+        match value {
+            GridError::Ragged { row, expected, found } => SchematicError::Ragged { line: row + 1, expected, found },
+        }
+    }
+}
+
+impl From<SchematicError> for std::io::Error {
+    fn from(value: SchematicError) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, value)
+    }
+}
+
+impl Options {
+    fn rules(&self) -> SchematicRules {
+        SchematicRules {
+            symbols: self.symbols.as_ref().map(|x| x.chars().collect()),
+            blank: self.blank,
+            gear: self.gear,
+            gear_parts: self.gear_parts,
+            gear_combine: self.gear_combine,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+enum GearCombine {
+    Product,
+    Sum,
+}
+
+struct SchematicRules {
+    // None means anything that isn't a digit or blank
+    symbols: Option<Vec<char>>,
+    blank: char,
+    gear: char,
+    gear_parts: usize,
+    gear_combine: GearCombine,
+}
+
+impl SchematicRules {
+    fn is_symbol(&self, item: char) -> bool {
+        match &self.symbols {
+            Some(symbols) => symbols.contains(&item),
+            None => !item.is_ascii_digit() && item != self.blank,
+        }
+    }
+
+    // None if the ratio doesn't fit in an i128, which a few big parts can manage
+    fn combine(&self, parts: &[i64]) -> Option<i128> {
+        match self.gear_combine {
+            GearCombine::Product => parts.iter().try_fold(1i128, |acc, x| acc.checked_mul(*x as i128)),
+            GearCombine::Sum => parts.iter().try_fold(0i128, |acc, x| acc.checked_add(*x as i128)),
+        }
+    }
 }
 
-struct Row {
-    inner: Vec<char>,
+struct EngineSchematic {
+    grid: Grid<char>,
+    rules: SchematicRules,
+    numbers: Vec<UnverifiedPartNumber>,
 }
 
 struct UnverifiedPartNumber {
@@ -27,226 +151,501 @@ struct UnverifiedGear {
 }
 
 struct Gear {
-    gear_ratio: i64,
+    gear_ratio: i128,
     locations: Coordinate
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-struct Coordinate {
-    row: usize,
-    column: usize
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum CellKind {
+    Blank,
+    PartNumber,
+    UnverifiedNumber,
+    Symbol,
+    Gear,
 }
 
-impl Coordinate {
-    fn get_neighboring(&self) -> Vec<Coordinate> {
-        let mut neighboring = Vec::new();
+impl CellKind {
+    fn colour(&self) -> Option<&'static str> {
+        match self {
+            Self::Blank => None,
+            Self::PartNumber => Some("\x1b[1;32m"),
+            Self::UnverifiedNumber => Some("\x1b[31m"),
+            Self::Symbol => Some("\x1b[33m"),
+            Self::Gear => Some("\x1b[1;35m"),
+        }
+    }
+}
 
-        match (self.row, self.column) {
-            (0, 0) => {
-                neighboring.push(Coordinate::from((1,0)));
-                neighboring.push(Coordinate::from((0,1)));
-                neighboring.push(Coordinate::from((1,1)));
-            },
-            (row, 0) => {
-                neighboring.push(Coordinate::from((row+1,1)));
-                neighboring.push(Coordinate::from((row+1,0)));
+impl EngineSchematic {
+    fn from(file: BufReader<File>, rules: SchematicRules, ragged: bool) -> Result<EngineSchematic, std::io::Error> {
+        let mut text = String::new();
+        for (row, line) in file.lines().enumerate() {
+            let validated_line = line?;
+            let validated_line = validated_line.strip_suffix('\r').unwrap_or(&validated_line);
 
-                neighboring.push(Coordinate::from((row-1,1)));
-                neighboring.push(Coordinate::from((row-1,0)));
+            if let Some((column, found)) = validated_line.chars().enumerate().find(|(_, x)| !x.is_ascii()) {
+                return Err(SchematicError::NonAscii { line: row + 1, column: column + 1, found }.into());
+            }
 
-                neighboring.push(Coordinate::from((row,1)));
-            },
-            (0, column) => {
-                neighboring.push(Coordinate::from((0, column-1)));
-                neighboring.push(Coordinate::from((0, column+1)));
+            text.push_str(validated_line);
+            text.push('\n');
+        }
 
-                neighboring.push(Coordinate::from((1,column-1)));
-                neighboring.push(Coordinate::from((1,column+1)));
+        // a trailing newline or two isn't part of the schematic
+        let text = text.trim_end_matches('\n');
 
-                neighboring.push(Coordinate::from((1,column)));
-            },
-            (row, column) => {
-                neighboring.push(Coordinate::from((row+1, column+1)));
-                neighboring.push(Coordinate::from((row-1, column-1)));
+        let grid = if ragged {
+            Grid::parse_padded(text, |x| x, rules.blank)
+        } else {
+            Grid::parse_chars(text).map_err(SchematicError::from)?
+        };
 
-                neighboring.push(Coordinate::from((row-1, column+1)));
-                neighboring.push(Coordinate::from((row+1, column-1)));
+        let mut numbers = Vec::new();
+        for (row_number, row) in grid.rows().enumerate() {
+            numbers.append(&mut Self::unverified_part_numbers(row, row_number)?);
+        }
 
-                neighboring.push(Coordinate::from((row+1, column)));
-                neighboring.push(Coordinate::from((row-1, column)));
+        Ok(EngineSchematic { grid, rules, numbers })
+    }
+
+    fn unverified_part_numbers(row: &[char], row_number: usize) -> Result<Vec<UnverifiedPartNumber>, SchematicError> {
+        let mut upns = Vec::new();
 
-                neighboring.push(Coordinate::from((row, column+1)));
-                neighboring.push(Coordinate::from((row, column-1)));
+        let mut column = 0;
+        while column < row.len() {
+            if !row[column].is_ascii_digit() {
+                column += 1;
+                continue;
             }
+
+            let (start, end, num) = number_at(row, column, row_number)?;
+            upns.push(UnverifiedPartNumber { num, locations: (start..end).map(|x| Coordinate::from((row_number, x))).collect() });
+            column = end;
         }
 
-        neighboring
+        Ok(upns)
     }
 
-    fn exceeds_bounds(&self, row_len: usize, col_len: usize) -> bool {
-        self.row >= row_len || self.column >= col_len
-    }
-}
+    fn get_verified_part_numbers(&self) -> Vec<PartNumber> {
+        let mut pns = Vec::new();
+
+        for upn in &self.numbers {
+            for coord in &upn.locations {
+                if self.coordinate_adjacent_symbol(*coord) {
+                    pns.push(PartNumber { num: upn.num, locations: upn.locations.clone() });
+                    break;
+                }      
+            }
+        }
 
-impl From<(usize, usize)> for Coordinate {
-    fn from(value: (usize, usize)) -> Self {
-        Coordinate { row: value.0, column: value.1 }
+        pns
     }
-}
 
+    fn coordinate_adjacent_symbol(&self, coord: Coordinate) -> bool {
+        self.grid.neighbours8(coord).any(|adj| self.rules.is_symbol(self[adj]))
+    }
 
-impl Row {
-    fn from(row: String) -> Row {
-        Row {
-            inner: row.chars().collect::<Vec<char>>()
-        }
+    fn get_unverified_gears(&self) -> Vec<UnverifiedGear> {
+        self.grid.coordinates()
+            .filter(|x| self[*x] == self.rules.gear)
+            .map(|location| UnverifiedGear { location })
+            .collect()
     }
 
-    fn get_unverified_part_numbers(&self, row_number: usize) -> Vec<UnverifiedPartNumber> {
-        let mut upns = Vec::new();
+    fn get_verified_gears(&self, map: HashMap<Coordinate, &PartNumber>) -> Result<Vec<Gear>, SchematicError> {
+        let mut verified_gears = Vec::new();
 
-        let mut buf = String::new();
-        let mut offsets = Vec::new();
-        for (loc, char) in self.inner.iter().enumerate() {
-            if !char.is_ascii_digit() {
-                if !buf.is_empty() {
-                    let num = str::parse::<i64>(&buf).expect("failed to parse string");
-                    upns.push(UnverifiedPartNumber { num, locations: offsets.iter().map(|x| Coordinate::from((row_number, *x))).collect() });
-                    buf = String::new();
-                    offsets = Vec::new();
+        for gear in self.get_unverified_gears() {
+            let mut adjacent_parts: HashMap<&PartNumber, ()> = HashMap::new();
+            for coord in self.grid.neighbours8(gear.location) {
+                if let Some(pn) = map.get(&coord) {
+                    adjacent_parts.insert(pn, ());
                 }
+            }
 
+            if adjacent_parts.len() == self.rules.gear_parts {
+                let parts: Vec<i64> = adjacent_parts.keys().map(|x| x.num).collect();
+                let gr = self.rules.combine(&parts).ok_or_else(|| ratio_overflow(gear.location))?;
+                verified_gears.push(Gear { gear_ratio: gr, locations: gear.location });
+            }
+        }
 
-                continue;
+        Ok(verified_gears)
+    }
+}
+
+impl EngineSchematic {
+    fn classify(&self) -> Result<(Grid<CellKind>, Vec<Gear>), SchematicError> {
+        let mut kinds = Grid::filled(self.grid.width(), self.grid.height(), CellKind::Blank);
+
+        for coord in self.grid.coordinates() {
+            if self[coord].is_ascii_digit() {
+                kinds[coord] = CellKind::UnverifiedNumber;
+            } else if self.rules.is_symbol(self[coord]) {
+                kinds[coord] = CellKind::Symbol;
             }
+        }
 
-            buf.push(*char);
-            offsets.push(loc);
-        };
+        let pns = self.get_verified_part_numbers();
+        for coord in pns.iter().flat_map(|x| &x.locations) {
+            kinds[*coord] = CellKind::PartNumber;
+        }
 
-        if !buf.is_empty() {
-            let num = str::parse::<i64>(&buf).expect("failed to parse string");
-            upns.push(UnverifiedPartNumber { num, locations: offsets.iter().map(|x| Coordinate::from((row_number, *x))).collect() });
+        let gears = self.get_verified_gears(convert_pns_to_map(&pns))?;
+        for gear in &gears {
+            kinds[gear.locations] = CellKind::Gear;
         }
 
-        upns
+        Ok((kinds, gears))
     }
 
-    fn get_unverified_gears(&self, row_number: usize) -> Vec<UnverifiedGear> {
-        let mut ugs = Vec::new();
+    // colours only make sense on a terminal, so anything else gets the plain
+    // schematic with the gear annotations
+    fn render(&self, colour: bool) -> Result<String, SchematicError> {
+        let (kinds, gears) = self.classify()?;
+        let mut out = String::new();
+
+        for row in 0..self.grid.height() {
+            let mut current = CellKind::Blank;
+            for column in 0..self.grid.width() {
+                let coord = Coordinate { row, column };
+                if colour && kinds[coord] != current {
+                    if current != CellKind::Blank {
+                        out.push_str("\x1b[0m");
+                    }
+                    out.push_str(kinds[coord].colour().unwrap_or(""));
+                    current = kinds[coord];
+                }
+                out.push(self[coord]);
+            }
+            if colour && current != CellKind::Blank {
+                out.push_str("\x1b[0m");
+            }
 
-        for (loc, char) in self.inner.iter().enumerate() {
-            if *char == '*' {
-                ugs.push(UnverifiedGear { location: Coordinate::from((row_number, loc)) });
+            let ratios: Vec<String> = gears.iter()
+                .filter(|x| x.locations.row == row)
+                .map(|x| format!("gear @ {}: {}", x.locations.column, x.gear_ratio))
+                .collect();
+            if !ratios.is_empty() {
+                out.push_str("  ");
+                out.push_str(&ratios.join(", "));
             }
+            out.push('\n');
         }
 
-        ugs
+        Ok(out)
     }
 }
 
-impl Index<usize> for Row {
+impl Index<Coordinate> for EngineSchematic {
     type Output = char;
 
-    fn index(&self, index: usize) -> &Self::Output {
-        &self.inner[index]
+    fn index(&self, index: Coordinate) -> &Self::Output {
+        &self.grid[index]
     }
 }
 
-impl EngineSchematic {
-    fn from(file: BufReader<File>) -> Result<EngineSchematic, std::io::Error> {
-        let mut rows = Vec::new();
-        for line in file.lines() {
-            let validated_line = line?;
+#[derive(Default)]
+struct StreamingTotals {
+    part_numbers: i128,
+    gear_ratios: i128,
+}
+
+// evaluates the schematic a row at a time, only ever holding the rows above
+// and below the one being looked at. relies on gears being symbols, so a
+// number next to a gear is always a verified part number
+struct StreamingEvaluator<'a> {
+    rules: &'a SchematicRules,
+    gear: u8,
+    parts: Vec<i64>,
+    totals: StreamingTotals,
+    // rectangular input checks, skipped for ragged schematics
+    ragged: bool,
+    width: Option<usize>,
+    blank_from: Option<usize>,
+}
 
-            rows.push(Row::from(validated_line));
+impl StreamingEvaluator<'_> {
+    fn evaluate<R: BufRead>(mut input: R, rules: &SchematicRules, ragged: bool) -> Result<StreamingTotals, std::io::Error> {
+        if !rules.gear.is_ascii() || !rules.is_symbol(rules.gear) {
+            return Err(SchematicError::StreamingGear(rules.gear).into());
         }
 
-        Ok(EngineSchematic { rows })
-    }
+        let mut evaluator = StreamingEvaluator {
+            rules,
+            gear: rules.gear as u8,
+            parts: Vec::new(),
+            totals: StreamingTotals::default(),
+            ragged,
+            width: None,
+            blank_from: None,
+        };
 
-    fn get_verified_part_numbers(&self) -> Vec<PartNumber> {
-        let mut full_upns = Vec::new();
-        let mut pns = Vec::new();
+        let mut above = Vec::new();
+        let mut current = Vec::new();
+        let mut below = Vec::new();
 
-        for (offset, row) in self.rows.iter().enumerate() {
-            let mut upns = row.get_unverified_part_numbers(offset);
-            full_upns.append(&mut upns);
+        let mut row_number = 0;
+        let mut has_current = read_row(&mut input, &mut current, row_number)?;
+        if has_current {
+            evaluator.check_width(&current, row_number)?;
         }
 
-        for upn in full_upns {
-            for coord in &upn.locations {
-                if self.coordinate_adjacent_symbol(*coord) {
-                    pns.push(PartNumber { num: upn.num, locations: upn.locations.clone() });
-                    break;
-                }      
+        while has_current {
+            let has_below = read_row(&mut input, &mut below, row_number + 1)?;
+            if has_below {
+                evaluator.check_width(&below, row_number + 1)?;
             }
+
+            evaluator.evaluate_row(&above, &current, &below, row_number)?;
+
+            // recycle the buffer that just fell out of the window
+            std::mem::swap(&mut above, &mut current);
+            std::mem::swap(&mut current, &mut below);
+            has_current = has_below;
+            row_number += 1;
         }
 
-        pns
+        Ok(evaluator.totals)
     }
 
-    fn coordinate_adjacent_symbol(&self, coord: Coordinate) -> bool {
-        for adj in coord.get_neighboring() {
-            if adj.exceeds_bounds(self.rows.len(), self.rows[0].inner.len()) {
+    // same rules as the grid: every row the same width, apart from blank lines at the end
+    fn check_width(&mut self, row: &[u8], row_number: usize) -> Result<(), SchematicError> {
+        if self.ragged {
+            return Ok(());
+        }
+
+        if row.is_empty() {
+            self.blank_from.get_or_insert(row_number);
+            return Ok(());
+        }
+
+        let expected = *self.width.get_or_insert(row.len());
+        if let Some(blank) = self.blank_from {
+            return Err(SchematicError::Ragged { line: blank + 1, expected, found: 0 });
+        }
+
+        if row.len() != expected {
+            return Err(SchematicError::Ragged { line: row_number + 1, expected, found: row.len() });
+        }
+
+        Ok(())
+    }
+
+    fn evaluate_row(&mut self, above: &[u8], current: &[u8], below: &[u8], row_number: usize) -> Result<(), SchematicError> {
+        let mut column = 0;
+        while column < current.len() {
+            if !current[column].is_ascii_digit() {
+                if current[column] == self.gear {
+                    self.evaluate_gear(above, current, below, column, row_number)?;
+                }
+                column += 1;
                 continue;
             }
-            if is_symbol(self[adj]) {
-                return true;
+
+            let (start, end, num) = number_at(current, column, row_number)?;
+            let from = start.saturating_sub(1);
+            if [above, current, below].iter().any(|row| self.any_symbol(row, from, end)) {
+                self.totals.part_numbers = self.totals.part_numbers.checked_add(num as i128)
+                    .ok_or(SchematicError::NumberOverflow { line: row_number + 1, column: start + 1 })?;
             }
+            column = end;
         }
 
-        false
+        Ok(())
     }
 
-    fn get_unverified_gears(&self) -> Vec<UnverifiedGear> {
-        let mut full_gears = Vec::new();
+    fn evaluate_gear(&mut self, above: &[u8], current: &[u8], below: &[u8], column: usize, row_number: usize) -> Result<(), SchematicError> {
+        self.parts.clear();
+
+        // above is empty on the first row, so the wrapping line number is never used
+        for (row, line) in [(above, row_number.wrapping_sub(1)), (current, row_number), (below, row_number + 1)] {
+            let from = column.saturating_sub(1);
+            let to = (column + 1).min(row.len().saturating_sub(1));
+            for touching in from..=to {
+                if touching >= row.len() || !row[touching].is_ascii_digit() {
+                    continue;
+                }
+                // only count a number once, from the leftmost digit inside the window
+                if touching > from && row[touching - 1].is_ascii_digit() {
+                    continue;
+                }
 
-        for (offset, row) in self.rows.iter().enumerate() {
-            let mut upns = row.get_unverified_gears(offset);
-            full_gears.append(&mut upns);
+                let mut start = touching;
+                while start > 0 && row[start - 1].is_ascii_digit() {
+                    start -= 1;
+                }
+                self.parts.push(number_at(row, start, line)?.2);
+            }
+        }
+
+        if self.parts.len() == self.rules.gear_parts {
+            let overflow = || SchematicError::RatioOverflow { line: row_number + 1, column: column + 1 };
+            let ratio = self.rules.combine(&self.parts).ok_or_else(overflow)?;
+            self.totals.gear_ratios = self.totals.gear_ratios.checked_add(ratio).ok_or_else(overflow)?;
         }
 
-        full_gears
+        Ok(())
     }
 
-    fn get_verified_gears(&self, map: HashMap<Coordinate, &PartNumber>) -> Vec<Gear> {
-        let mut full_gears = Vec::new();
-        let mut verified_gears = Vec::new();
+    // inclusive column range, clamped to the row
+    fn any_symbol(&self, row: &[u8], from: usize, to: usize) -> bool {
+        row.iter().take(to + 1).skip(from).any(|x| !x.is_ascii_digit() && self.rules.is_symbol(*x as char))
+    }
+}
+
+fn number_overflow(location: Coordinate) -> SchematicError {
+    SchematicError::NumberOverflow { line: location.row + 1, column: location.column + 1 }
+}
+
+fn ratio_overflow(location: Coordinate) -> SchematicError {
+    SchematicError::RatioOverflow { line: location.row + 1, column: location.column + 1 }
+}
+
+// the digit run starting at start, as (start, end exclusive, value), for
+// streamed byte rows and whole-grid char rows alike
+fn number_at<T: Copy + Into<char>>(row: &[T], start: usize, row_number: usize) -> Result<(usize, usize, i64), SchematicError> {
+    let mut end = start;
+    let mut num: i64 = 0;
+
+    while let Some(digit) = row.get(end).and_then(|x| (*x).into().to_digit(10)) {
+        num = num.checked_mul(10)
+            .and_then(|x| x.checked_add(digit as i64))
+            .ok_or(SchematicError::NumberOverflow { line: row_number + 1, column: start + 1 })?;
+        end += 1;
+    }
+
+    Ok((start, end, num))
+}
+
+// reads the next line into buf without its line ending, false at end of input
+fn read_row<R: BufRead>(input: &mut R, buf: &mut Vec<u8>, row_number: usize) -> Result<bool, std::io::Error> {
+    buf.clear();
+    if input.read_until(b'\n', buf)? == 0 {
+        return Ok(false);
+    }
 
-        for (offset, row) in self.rows.iter().enumerate() {
-            let mut upns = row.get_unverified_gears(offset);
-            full_gears.append(&mut upns);
+    if buf.last() == Some(&b'\n') {
+        buf.pop();
+    }
+    if buf.last() == Some(&b'\r') {
+        buf.pop();
+    }
+
+    if let Some(column) = buf.iter().position(|x| !x.is_ascii()) {
+        // everything before it is ascii, so the byte offset is the character column
+        let found = String::from_utf8_lossy(&buf[column..]).chars().next().unwrap_or(char::REPLACEMENT_CHARACTER);
+        return Err(SchematicError::NonAscii { line: row_number + 1, column: column + 1, found }.into());
+    }
+
+    Ok(true)
+}
+
+#[derive(Default)]
+struct SymbolStats {
+    occurrences: usize,
+    adjacent_part_numbers: usize,
+}
+
+struct SchematicStats {
+    symbols: BTreeMap<char, SymbolStats>,
+    numbers: usize,
+    part_numbers: usize,
+    isolated_numbers: usize,
+    multi_symbol_numbers: usize,
+    // index n is gears with n adjacent part numbers, the last bucket is 3 or more
+    gears_by_parts: [usize; 4],
+}
+
+impl SchematicStats {
+    fn collect(schematic: &EngineSchematic) -> SchematicStats {
+        let pns = schematic.get_verified_part_numbers();
+        let mapping = convert_pns_to_map(&pns);
+        let adjacent_parts = |coord: Coordinate| -> HashSet<&PartNumber> {
+            schematic.grid.neighbours8(coord).filter_map(|x| mapping.get(&x).copied()).collect()
+        };
+
+        let mut symbols: BTreeMap<char, SymbolStats> = BTreeMap::new();
+        for coord in schematic.grid.coordinates().filter(|x| schematic.rules.is_symbol(schematic[*x])) {
+            let entry = symbols.entry(schematic[coord]).or_default();
+            entry.occurrences += 1;
+            entry.adjacent_part_numbers += adjacent_parts(coord).len();
         }
 
-        for gear in full_gears {
-            let mut adjacent_parts: HashMap<&PartNumber, ()> = HashMap::new();
-            let adj = gear.location.get_neighboring();
-            for coord in adj {
-                if let Some(pn) = map.get(&coord) {
-                    adjacent_parts.insert(pn, ());
-                }
-            }
+        let numbers = schematic.numbers.len();
 
-            if adjacent_parts.len() == 2 {
-                let gr = adjacent_parts.keys().fold(1, |acc, k| acc * k.num);
-                verified_gears.push(Gear { gear_ratio: gr, locations: gear.location });
-            }
+        let multi_symbol_numbers = pns.iter().filter(|pn| {
+            let symbols: HashSet<Coordinate> = pn.locations.iter()
+                .flat_map(|x| schematic.grid.neighbours8(*x))
+                .filter(|x| schematic.rules.is_symbol(schematic[*x]))
+                .collect();
+            symbols.len() > 1
+        }).count();
+
+        let mut gears_by_parts = [0; 4];
+        for gear in schematic.get_unverified_gears() {
+            gears_by_parts[adjacent_parts(gear.location).len().min(3)] += 1;
         }
 
-        verified_gears
+        SchematicStats {
+            symbols,
+            numbers,
+            part_numbers: pns.len(),
+            isolated_numbers: numbers - pns.len(),
+            multi_symbol_numbers,
+            gears_by_parts,
+        }
+    }
+
+    fn to_json(&self) -> String {
+        let symbols = self.symbols.iter()
+            .map(|(symbol, stats)| format!("{{\"symbol\": {}, \"occurrences\": {}, \"adjacent_part_numbers\": {}}}", json_string(*symbol), stats.occurrences, stats.adjacent_part_numbers))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        format!(
+            "{{\"symbols\": [{}], \"numbers\": {}, \"part_numbers\": {}, \"isolated_numbers\": {}, \"multi_symbol_numbers\": {}, \"gears\": {{\"0\": {}, \"1\": {}, \"2\": {}, \"3+\": {}}}}}",
+            symbols, self.numbers, self.part_numbers, self.isolated_numbers, self.multi_symbol_numbers,
+            self.gears_by_parts[0], self.gears_by_parts[1], self.gears_by_parts[2], self.gears_by_parts[3]
+        )
     }
 }
 
-fn is_symbol(item: char) -> bool {
-    !item.is_ascii_digit() && item != '.'
+impl Display for SchematicStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{:<8}{:>12}{:>16}", "symbol", "occurrences", "adjacent parts")?;
+        for (symbol, stats) in &self.symbols {
+            writeln!(f, "{:<8}{:>12}{:>16}", symbol, stats.occurrences, stats.adjacent_part_numbers)?;
+        }
+        writeln!(f)?;
+        writeln!(f, "{:<28}{:>8}", "numbers", self.numbers)?;
+        writeln!(f, "{:<28}{:>8}", "part numbers", self.part_numbers)?;
+        writeln!(f, "{:<28}{:>8}", "isolated numbers", self.isolated_numbers)?;
+        writeln!(f, "{:<28}{:>8}", "next to multiple symbols", self.multi_symbol_numbers)?;
+        writeln!(f)?;
+        writeln!(f, "{:<28}{:>8}", "gears with 0 parts", self.gears_by_parts[0])?;
+        writeln!(f, "{:<28}{:>8}", "gears with 1 part", self.gears_by_parts[1])?;
+        writeln!(f, "{:<28}{:>8}", "gears with 2 parts", self.gears_by_parts[2])?;
+        writeln!(f, "{:<28}{:>8}", "gears with 3+ parts", self.gears_by_parts[3])
+    }
 }
 
-impl Index<Coordinate> for EngineSchematic {
-    type Output = char;
+// a one-character json string, control characters and delete as \u escapes
+fn json_string(symbol: char) -> String {
+    match symbol {
+        '"' => String::from("\"\\\"\""),
+        '\\' => String::from("\"\\\\\""),
+        x if x.is_ascii_control() => format!("\"\\u{:04x}\"", x as u32),
+        x => format!("\"{}\"", x),
+    }
+}
 
-    fn index(&self, index: Coordinate) -> &Self::Output {
-        &self.rows[index.row][index.column]
+fn print_stats(schematic: &EngineSchematic, format: StatsFormat) {
+    let stats = SchematicStats::collect(schematic);
+
+    match format {
+        StatsFormat::Table => print!("{}", stats),
+        StatsFormat::Json => println!("{}", stats.to_json()),
     }
 }
 
@@ -262,12 +661,27 @@ fn convert_pns_to_map(pns: &[PartNumber]) -> HashMap<Coordinate, &PartNumber> {
     res
 }
 
-pub fn question_one(in_file: BufReader<File>) -> Result<(), std::io::Error> {
-    let engine_schematic = EngineSchematic::from(in_file)?;
+pub fn question_one(in_file: BufReader<File>, options: &Options) -> Result<(), std::io::Error> {
+    if options.stream {
+        let totals = StreamingEvaluator::evaluate(in_file, &options.rules(), options.ragged)?;
+        println!("total: {}", totals.part_numbers);
+        return Ok(());
+    }
+
+    let engine_schematic = EngineSchematic::from(in_file, options.rules(), options.ragged)?;
 
-    let mut total = 0;
+    if let Some(format) = options.stats {
+        print_stats(&engine_schematic, format);
+        return Ok(());
+    }
+
+    if options.render {
+        print!("{}", engine_schematic.render(std::io::stdout().is_terminal())?);
+    }
+
+    let mut total: i128 = 0;
     for pn in engine_schematic.get_verified_part_numbers() {
-        total += pn.num;
+        total = total.checked_add(pn.num as i128).ok_or_else(|| number_overflow(pn.locations[0]))?;
     }
     
     println!("total: {}", total);
@@ -275,17 +689,32 @@ pub fn question_one(in_file: BufReader<File>) -> Result<(), std::io::Error> {
     Ok(())
 }
 
-pub fn question_two(in_file: BufReader<File>) -> Result<(), std::io::Error> {
-    let engine_schematic = EngineSchematic::from(in_file)?;
+pub fn question_two(in_file: BufReader<File>, options: &Options) -> Result<(), std::io::Error> {
+    if options.stream {
+        let totals = StreamingEvaluator::evaluate(in_file, &options.rules(), options.ragged)?;
+        println!("total: {}", totals.gear_ratios);
+        return Ok(());
+    }
+
+    let engine_schematic = EngineSchematic::from(in_file, options.rules(), options.ragged)?;
+
+    if let Some(format) = options.stats {
+        print_stats(&engine_schematic, format);
+        return Ok(());
+    }
+
+    if options.render {
+        print!("{}", engine_schematic.render(std::io::stdout().is_terminal())?);
+    }
 
     let pns = engine_schematic.get_verified_part_numbers();
     let mapping = convert_pns_to_map(&pns);
-    let mut total = 0;
-    for pn in engine_schematic.get_verified_gears(mapping) {
-        total += pn.gear_ratio;
+    let mut total: i128 = 0;
+    for pn in engine_schematic.get_verified_gears(mapping)? {
+        total = total.checked_add(pn.gear_ratio).ok_or_else(|| ratio_overflow(pn.locations))?;
     }
     
     println!("total: {}", total);
 
     Ok(())
-}
\ No newline at end of file
+}
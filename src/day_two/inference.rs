@@ -0,0 +1,213 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+use super::{Bag, Game};
+
+// every reveal is treated as cubes drawn with replacement from one fixed bag, so
+// only the colour proportions are identifiable - counts come from scaling them
+// to a bag size, by default the size of the minimal bag
+pub struct BagEstimate {
+    game_id: i64,
+    reveals: usize,
+    drawn: i64,
+    size: i64,
+    level: f64,
+    colours: Vec<ColourEstimate>,
+}
+
+struct ColourEstimate {
+    colour: String,
+    minimum: i64,
+    likely: i64,
+    share: f64,
+    lower: i64,
+    upper: i64,
+}
+
+pub fn infer(game: &Game, bag: &Bag, size: Option<i64>, level: f64) -> BagEstimate {
+    let minimum = game.minimum_bag();
+
+    let mut drawn: BTreeMap<&str, i64> = bag.limits.keys().map(|x| (x.as_str(), 0)).collect();
+    for subgame in &game.subgames {
+        for (colour, count) in &subgame.cubes {
+            *drawn.entry(colour.as_str()).or_insert(0) += count;
+        }
+    }
+
+    let total: i64 = drawn.values().sum();
+    let observed = drawn.values().filter(|x| **x > 0).count() as i64;
+    // every colour that was drawn needs at least one cube
+    let size = size.unwrap_or_else(|| minimum.values().sum()).max(observed);
+
+    let counts: Vec<i64> = drawn.values().copied().collect();
+    let likely = most_likely_counts(&counts, size);
+
+    // flat dirichlet prior, so each share has a beta(x + 1, total - x + k - 1) posterior
+    let others = counts.len() as i64 - 1;
+    let tail = (1.0 - level) / 2.0;
+
+    let colours = drawn.iter().zip(likely).map(|((colour, count), likely)| {
+        let (a, b) = (count + 1, total - count + others);
+
+        ColourEstimate {
+            colour: colour.to_string(),
+            minimum: minimum.get(colour).copied().unwrap_or(0),
+            likely,
+            share: if total == 0 { 0.0 } else { *count as f64 / total as f64 },
+            lower: (beta_quantile(a, b, tail) * size as f64).floor() as i64,
+            upper: (beta_quantile(a, b, 1.0 - tail) * size as f64).ceil() as i64,
+        }
+    }).collect();
+
+    BagEstimate { game_id: game.game_id, reveals: game.subgames.len(), drawn: total, size, level, colours }
+}
+
+// the likelihood is the sum of x * ln(c), which is concave in each c, so an
+// allocation no single cube can move out of without losing likelihood is the
+// best one. starting from the proportional shares only a few moves are needed
+fn most_likely_counts(drawn: &[i64], size: i64) -> Vec<i64> {
+    let total: i64 = drawn.iter().sum();
+    // every colour that was drawn needs at least one cube
+    let mut counts: Vec<i64> = drawn.iter()
+        .map(|x| if *x > 0 { ((*x as i128 * size as i128 / total as i128) as i64).max(1) } else { 0 })
+        .collect();
+
+    // what one more cube is worth to colour i, and what taking one away costs
+    let gain = |counts: &[i64], i: usize| drawn[i] as f64 * (1.0 / counts[i] as f64).ln_1p();
+    let loss = |counts: &[i64], i: usize| drawn[i] as f64 * (1.0 / (counts[i] - 1) as f64).ln_1p();
+    let best_gain = |counts: &[i64]| (0..drawn.len())
+        .filter(|i| drawn[*i] > 0)
+        .max_by(|a, b| gain(counts, *a).total_cmp(&gain(counts, *b)));
+    let least_loss = |counts: &[i64]| (0..drawn.len())
+        .filter(|i| counts[*i] > 1)
+        .min_by(|a, b| loss(counts, *a).total_cmp(&loss(counts, *b)));
+
+    let mut allocated: i64 = counts.iter().sum();
+    // rounding the shares up to one cube can overshoot the bag
+    while allocated > size {
+        match least_loss(&counts) {
+            Some(i) => counts[i] -= 1,
+            None => break,
+        }
+        allocated -= 1;
+    }
+    while allocated < size {
+        match best_gain(&counts) {
+            Some(i) => counts[i] += 1,
+            None => break,
+        }
+        allocated += 1;
+    }
+
+    // then move single cubes while that still helps
+    while let (Some(to), Some(from)) = (best_gain(&counts), least_loss(&counts)) {
+        if to == from || gain(&counts, to) <= loss(&counts, from) {
+            break;
+        }
+        counts[to] += 1;
+        counts[from] -= 1;
+    }
+
+    counts
+}
+
+// with integer parameters the beta cdf at p is the regularized incomplete beta
+// function, so the quantile is found by bisecting on that
+fn beta_quantile(a: i64, b: i64, q: f64) -> f64 {
+    // a single colour takes every cube
+    if b == 0 {
+        return 1.0;
+    }
+
+    let (mut low, mut high) = (0.0, 1.0);
+    for _ in 0..100 {
+        let mid = (low + high) / 2.0;
+        if incomplete_beta(a as f64, b as f64, mid) < q {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+
+    (low + high) / 2.0
+}
+
+// the continued fraction converges quickly below the mean, so above it the
+// symmetry i(x; a, b) = 1 - i(1 - x; b, a) is used instead
+fn incomplete_beta(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+
+    let ln_front = ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln();
+    if x < (a + 1.0) / (a + b + 2.0) {
+        ln_front.exp() * beta_fraction(a, b, x) / a
+    } else {
+        1.0 - ln_front.exp() * beta_fraction(b, a, 1.0 - x) / b
+    }
+}
+
+// lentz's method, which needs on the order of sqrt(max(a, b)) terms
+fn beta_fraction(a: f64, b: f64, x: f64) -> f64 {
+    const TINY: f64 = 1e-300;
+    let not_tiny = |x: f64| if x.abs() < TINY { TINY } else { x };
+
+    let mut c = 1.0;
+    let mut d = 1.0 / not_tiny(1.0 - (a + b) * x / (a + 1.0));
+    let mut fraction = d;
+
+    for m in 1..1_000_000 {
+        let m = m as f64;
+        let even = m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m));
+        d = 1.0 / not_tiny(1.0 + even * d);
+        c = not_tiny(1.0 + even / c);
+        fraction *= d * c;
+
+        let odd = -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0));
+        d = 1.0 / not_tiny(1.0 + odd * d);
+        c = not_tiny(1.0 + odd / c);
+        fraction *= d * c;
+
+        if (d * c - 1.0).abs() < 1e-15 {
+            break;
+        }
+    }
+
+    fraction
+}
+
+// lanczos approximation, good to about 15 digits for x >= 1
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9, 676.520_368_121_885_1, -1_259.139_216_722_402_8,
+        771.323_428_777_653_1, -176.615_029_162_140_6, 12.507_343_278_686_905,
+        -0.138_571_095_265_720_12, 9.984_369_578_019_572e-6, 1.505_632_735_149_311_6e-7,
+    ];
+
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let series = COEFFICIENTS.iter().skip(1).enumerate().fold(COEFFICIENTS[0], |acc, (i, c)| acc + c / (x + i as f64 + 1.0));
+
+    0.5 * std::f64::consts::TAU.ln() + (x + 0.5) * t.ln() - t + series.ln()
+}
+
+impl Display for BagEstimate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Game {}: {} reveals, {} cubes drawn, bag of {}", self.game_id, self.reveals, self.drawn, self.size)?;
+
+        let width = self.colours.iter().map(|x| x.colour.len()).max().unwrap_or(0);
+        for colour in &self.colours {
+            writeln!(
+                f,
+                "  {:<width$}  minimum {}, most likely {} ({:.1}%), {}% credible {}..{}",
+                colour.colour, colour.minimum, colour.likely, colour.share * 100.0,
+                self.level * 100.0, colour.lower, colour.upper,
+                width = width
+            )?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,384 @@
+use std::{io::{BufReader, BufRead}, fs::File, collections::HashMap, fmt::Display, error::Error, str::FromStr};
+
+use clap::{Args, ValueEnum};
+
+use crate::config;
+
+mod inference;
+mod query;
+
+#[derive(Args)]
+#[group(id = "day_two")]
+pub struct Options {
+    /// Day two: bag limit as COLOUR=COUNT, overriding the config file and defaults (repeatable)
+    #[clap(long = "limit")]
+    limit: Vec<BagLimit>,
+    /// Day two: read bag limits from a file of COLOUR=COUNT lines instead of the 12 red, 13 green, 14 blue default
+    #[clap(long = "limits")]
+    limits: Option<String>,
+    /// Day two: estimate each bag's contents from its reveals, treating them as draws with replacement
+    #[clap(long = "infer")]
+    infer: bool,
+    /// Day two: bag size to scale the inferred proportions to, defaults to the size of the minimal bag
+    #[clap(long = "bag-size", requires = "infer")]
+    bag_size: Option<i64>,
+    /// Day two: probability mass of the credible interval reported by --infer
+    #[clap(long = "level", requires = "infer", default_value_t = 0.95, value_parser = parse_level)]
+    level: f64,
+    /// Day two: only count games matching this expression, e.g. "max(red) <= 12 and sum(blue) > 20"; replaces the bag check in question one
+    #[clap(long = "query")]
+    query: Option<query::Query>,
+    /// Day two: reject malformed games (strict) or report them as warnings and carry on (lenient)
+    #[clap(long = "parse", value_enum, default_value_t = ParseMode::Lenient)]
+    parse: ParseMode,
+}
+
+fn parse_level(s: &str) -> Result<f64, String> {
+    let level = s.parse::<f64>().map_err(|e| e.to_string())?;
+
+    if level > 0.0 && level < 1.0 {
+        Ok(level)
+    } else {
+        Err(format!("{} is not strictly between 0 and 1", level))
+    }
+}
+
+#[derive(Clone)]
+struct BagLimit {
+    colour: String,
+    count: i64,
+}
+
+impl FromStr for BagLimit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (colour, count) = s.split_once('=').ok_or_else(|| format!("expected colour=count, got {}", s))?;
+        let count = count.trim().parse::<i64>().map_err(|e| format!("bad count for {}: {}", colour, e))?;
+
+        Ok(BagLimit { colour: colour.trim().to_owned(), count })
+    }
+}
+
+#[derive(Debug)]
+enum BagError {
+    Malformed { line: usize, reason: String },
+    CountOverflow { line: usize, colour: String },
+    PowerOverflow { line: usize },
+    TotalOverflow { line: usize },
+}
+
+impl Display for BagError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Malformed { line, reason } => write!(f, "line {}: {}", line, reason),
+            Self::CountOverflow { line, colour } => write!(f, "line {}: too many {} cubes in one reveal", line, colour),
+            Self::PowerOverflow { line } => write!(f, "line {}: power of the minimal bag is too large", line),
+            Self::TotalOverflow { line } => write!(f, "line {}: result is too large", line),
+        }
+    }
+}
+
+impl Error for BagError {}
+
+impl From<BagError> for std::io::Error {
+    fn from(value: BagError) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, value)
+    }
+}
+
+// how many cubes of each colour the bag holds, colours it doesn't mention it has none of
+struct Bag {
+    limits: HashMap<String, i64>,
+}
+
+impl Bag {
+    fn from_options(options: &Options) -> Result<Bag, std::io::Error> {
+        let mut bag = match &options.limits {
+            Some(path) => Bag::from_file(BufReader::new(File::open(path)?))?,
+            None => Bag::default(),
+        };
+
+        for limit in &options.limit {
+            bag.limits.insert(limit.colour.clone(), limit.count);
+        }
+
+        Ok(bag)
+    }
+
+    fn from_file(in_file: BufReader<File>) -> Result<Bag, std::io::Error> {
+        let mut limits = HashMap::new();
+
+        for entry in config::entries(in_file) {
+            let (line, text) = entry?;
+            let limit = text.parse::<BagLimit>().map_err(|reason| BagError::Malformed { line, reason })?;
+            limits.insert(limit.colour, limit.count);
+        }
+
+        Ok(Bag { limits })
+    }
+
+    fn allows(&self, subgame: &SubGame) -> bool {
+        subgame.cubes.iter().all(|(colour, count)| *count <= self.limits.get(colour).copied().unwrap_or(0))
+    }
+}
+
+impl Default for Bag {
+    fn default() -> Self {
+        let limits = [("red", 12), ("green", 13), ("blue", 14)].into_iter()
+            .map(|(colour, count)| (colour.to_owned(), count))
+            .collect();
+
+        Bag { limits }
+    }
+}
+
+struct Game {
+    line: usize,
+    game_id: i64,
+    subgames: Vec<SubGame>,
+}
+
+impl Game {
+    // the fewest cubes of each colour that make every reveal possible
+    fn minimum_bag(&self) -> HashMap<&str, i64> {
+        let mut minimum = HashMap::new();
+
+        for subgame in &self.subgames {
+            for (colour, count) in &subgame.cubes {
+                let entry = minimum.entry(colour.as_str()).or_insert(0);
+                *entry = (*entry).max(*count);
+            }
+        }
+
+        minimum
+    }
+
+    // a colour in the bag that never shows up needs zero cubes, so the power is zero
+    fn power(&self, bag: &Bag) -> Result<i64, BagError> {
+        let minimum = self.minimum_bag();
+        let missing = bag.limits.keys().any(|colour| !minimum.contains_key(colour.as_str()));
+
+        if missing {
+            Ok(0)
+        } else {
+            minimum.values()
+                .try_fold(1i64, |acc, x| acc.checked_mul(*x))
+                .ok_or(BagError::PowerOverflow { line: self.line })
+        }
+    }
+}
+
+struct SubGame {
+    cubes: HashMap<String, i64>,
+}
+
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq)]
+enum ParseMode {
+    // the first structural problem is an error
+    Strict,
+    // problems are worked around and reported as warnings
+    Lenient,
+}
+
+#[derive(Debug)]
+enum ParseIssue {
+    MissingHeader { line: usize },
+    NonSequentialId { line: usize, expected: i64, found: i64 },
+    EmptyReveal { line: usize, reveal: usize },
+    DuplicateColour { line: usize, reveal: usize, colour: String },
+    BadCubeSet { line: usize, reveal: usize, text: String },
+}
+
+impl Display for ParseIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingHeader { line } => write!(f, "line {}: missing \"Game N:\" header", line),
+            Self::NonSequentialId { line, expected, found } => write!(f, "line {}: expected game {}, found game {}", line, expected, found),
+            Self::EmptyReveal { line, reveal } => write!(f, "line {}: reveal {} is empty", line, reveal),
+            Self::DuplicateColour { line, reveal, colour } => write!(f, "line {}: reveal {} lists {} more than once", line, reveal, colour),
+            Self::BadCubeSet { line, reveal, text } => write!(f, "line {}: reveal {} has \"{}\", expected \"COUNT COLOUR\"", line, reveal, text),
+        }
+    }
+}
+
+impl Error for ParseIssue {}
+
+impl From<ParseIssue> for std::io::Error {
+    fn from(value: ParseIssue) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, value)
+    }
+}
+
+struct GameParser {
+    mode: ParseMode,
+    previous_id: Option<i64>,
+    warnings: Vec<ParseIssue>,
+}
+
+impl GameParser {
+    fn new(mode: ParseMode) -> GameParser {
+        GameParser { mode, previous_id: None, warnings: Vec::new() }
+    }
+
+    fn report(&mut self, issue: ParseIssue) -> Result<(), ParseIssue> {
+        match self.mode {
+            ParseMode::Strict => Err(issue),
+            ParseMode::Lenient => {
+                self.warnings.push(issue);
+                Ok(())
+            },
+        }
+    }
+
+    // blank lines are skipped
+    fn read_games(mut self, in_file: BufReader<File>) -> Result<(Vec<Game>, Vec<ParseIssue>), std::io::Error> {
+        let mut games: Vec<Game> = Vec::new();
+
+        for (index, line) in in_file.lines().enumerate() {
+            let validated_line = line?;
+            if validated_line.trim().is_empty() {
+                continue;
+            }
+            games.push(self.build_game(index + 1, &validated_line)?);
+        }
+
+        Ok((games, self.warnings))
+    }
+
+    fn build_game(&mut self, line: usize, text: &str) -> Result<Game, std::io::Error> {
+        let expected = self.previous_id.map(|x| x + 1);
+
+        let header = text.split_once(':').and_then(|(header, reveals)| {
+            let game_id = header.trim().strip_prefix("Game")?.trim().parse::<i64>().ok()?;
+            Some((game_id, reveals))
+        });
+
+        // without a header the id is assumed to follow on from the last game
+        let (game_id, reveals) = match header {
+            Some(found) => found,
+            None => {
+                self.report(ParseIssue::MissingHeader { line })?;
+                let reveals = text.split_once(':').map(|(_, x)| x).unwrap_or(text);
+                (expected.unwrap_or(1), reveals)
+            },
+        };
+
+        if let Some(expected) = expected.filter(|x| *x != game_id) {
+            self.report(ParseIssue::NonSequentialId { line, expected, found: game_id })?;
+        }
+        self.previous_id = Some(game_id);
+
+        let mut subgames: Vec<SubGame> = Vec::new();
+        for (index, subgame) in reveals.split(';').enumerate() {
+            if let Some(subgame) = self.build_subgame(line, index + 1, subgame)? {
+                subgames.push(subgame);
+            }
+        }
+
+        Ok(Game { line, game_id, subgames })
+    }
+
+    // empty reveals are dropped when lenient
+    fn build_subgame(&mut self, line: usize, reveal: usize, subgame: &str) -> Result<Option<SubGame>, std::io::Error> {
+        if subgame.trim().is_empty() {
+            self.report(ParseIssue::EmptyReveal { line, reveal })?;
+            return Ok(None);
+        }
+
+        let mut cubes: HashMap<String, i64> = HashMap::new();
+
+        for cube_set in subgame.split(',') {
+            let (colour, count) = match parse_cube_set(cube_set) {
+                Some(parsed) => parsed,
+                None => {
+                    self.report(ParseIssue::BadCubeSet { line, reveal, text: cube_set.trim().to_owned() })?;
+                    continue;
+                },
+            };
+
+            // lenient keeps the old behaviour of adding repeats together
+            if cubes.contains_key(&colour) {
+                self.report(ParseIssue::DuplicateColour { line, reveal, colour: colour.clone() })?;
+            }
+            let total = cubes.get(&colour).map_or(Some(count), |x| x.checked_add(count))
+                .ok_or_else(|| BagError::CountOverflow { line, colour: colour.clone() })?;
+            cubes.insert(colour, total);
+        }
+
+        Ok(Some(SubGame { cubes }))
+    }
+}
+
+fn parse_cube_set(cube_set: &str) -> Option<(String, i64)> {
+    let cubes = cube_set.split_whitespace().collect::<Vec<&str>>();
+    let (count, colour) = match cubes[..] {
+        [count, colour] => (count, colour),
+        _ => return None,
+    };
+    let cube_count = str::parse::<i64>(count).ok().filter(|x| *x >= 0)?;
+
+    Some((colour.to_owned(), cube_count))
+}
+
+fn print_warnings(warnings: &[ParseIssue]) {
+    for warning in warnings {
+        eprintln!("warning: {}", warning);
+    }
+}
+
+pub fn question_one(in_file: BufReader<File>, options: &Options) -> Result<(), std::io::Error> {
+    let bag = Bag::from_options(options)?;
+
+    let (games, warnings) = GameParser::new(options.parse).read_games(in_file)?;
+
+    let possible = |game: &Game| match &options.query {
+        Some(query) => query.matches(game),
+        None => game.subgames.iter().all(|subgame| bag.allows(subgame)),
+    };
+    let matched: Vec<&Game> = games.iter().filter(|x| possible(x)).collect();
+
+    if options.query.is_some() {
+        println!("matched {} of {} games", matched.len(), games.len());
+    }
+
+    let mut result: i64 = 0;
+    for game in matched {
+        result = result.checked_add(game.game_id).ok_or(BagError::TotalOverflow { line: game.line })?;
+    }
+
+    print_warnings(&warnings);
+
+    print!("result: {}", result);
+
+    Ok(())
+}
+
+pub fn question_two(in_file: BufReader<File>, options: &Options) -> Result<(), std::io::Error> {
+    let bag = Bag::from_options(options)?;
+
+    let (mut games, warnings) = GameParser::new(options.parse).read_games(in_file)?;
+
+    if let Some(query) = &options.query {
+        let total = games.len();
+        games.retain(|x| query.matches(x));
+        println!("matched {} of {} games", games.len(), total);
+    }
+
+    if options.infer {
+        for game in &games {
+            print!("{}", inference::infer(game, &bag, options.bag_size, options.level));
+        }
+    }
+
+    let mut result: i64 = 0;
+    for game in &games {
+        result = result.checked_add(game.power(&bag)?).ok_or(BagError::TotalOverflow { line: game.line })?;
+    }
+
+    print_warnings(&warnings);
+
+    print!("result: {}", result);
+
+    Ok(())
+}
@@ -0,0 +1,205 @@
+use std::str::FromStr;
+
+use nom::{
+    IResult,
+    branch::alt,
+    bytes::complete::{tag, take_while1},
+    character::complete::{char, digit1, multispace0, satisfy},
+    combinator::{all_consuming, map, map_res, not, value},
+    multi::many0,
+    sequence::{delimited, pair, preceded, terminated, tuple},
+};
+
+use super::Game;
+
+// e.g. `max(red) <= 12 and not (sum(blue) > 20 or reveals < 3)`
+#[derive(Clone, Debug)]
+pub enum Query {
+    Or(Box<Query>, Box<Query>),
+    And(Box<Query>, Box<Query>),
+    Not(Box<Query>),
+    Compare(Value, Comparison, Value),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Comparison {
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Equal,
+    NotEqual,
+}
+
+#[derive(Clone, Debug)]
+pub enum Value {
+    Number(i64),
+    Id,
+    Reveals,
+    Aggregate(Aggregate, String),
+    Add(Box<Value>, Box<Value>),
+    Subtract(Box<Value>, Box<Value>),
+    Multiply(Box<Value>, Box<Value>),
+}
+
+// over every reveal of a game, a colour missing from a reveal counts as zero
+#[derive(Clone, Copy, Debug)]
+pub enum Aggregate {
+    Max,
+    Min,
+    Sum,
+    // reveals that show the colour at all
+    Count,
+}
+
+impl Query {
+    pub fn matches(&self, game: &Game) -> bool {
+        match self {
+            Self::Or(lhs, rhs) => lhs.matches(game) || rhs.matches(game),
+            Self::And(lhs, rhs) => lhs.matches(game) && rhs.matches(game),
+            Self::Not(inner) => !inner.matches(game),
+            Self::Compare(lhs, comparison, rhs) => {
+                let (lhs, rhs) = (lhs.evaluate(game), rhs.evaluate(game));
+
+                match comparison {
+                    Comparison::Less => lhs < rhs,
+                    Comparison::LessEqual => lhs <= rhs,
+                    Comparison::Greater => lhs > rhs,
+                    Comparison::GreaterEqual => lhs >= rhs,
+                    Comparison::Equal => lhs == rhs,
+                    Comparison::NotEqual => lhs != rhs,
+                }
+            },
+        }
+    }
+}
+
+impl Value {
+    fn evaluate(&self, game: &Game) -> i64 {
+        match self {
+            Self::Number(x) => *x,
+            Self::Id => game.game_id,
+            Self::Reveals => game.subgames.len() as i64,
+            Self::Aggregate(aggregate, colour) => {
+                let counts = game.subgames.iter().map(|x| x.cubes.get(colour).copied().unwrap_or(0));
+
+                match aggregate {
+                    Aggregate::Max => counts.max().unwrap_or(0),
+                    Aggregate::Min => counts.min().unwrap_or(0),
+                    Aggregate::Sum => counts.sum(),
+                    Aggregate::Count => counts.filter(|x| *x > 0).count() as i64,
+                }
+            },
+            Self::Add(lhs, rhs) => lhs.evaluate(game).saturating_add(rhs.evaluate(game)),
+            Self::Subtract(lhs, rhs) => lhs.evaluate(game).saturating_sub(rhs.evaluate(game)),
+            Self::Multiply(lhs, rhs) => lhs.evaluate(game).saturating_mul(rhs.evaluate(game)),
+        }
+    }
+}
+
+impl FromStr for Query {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match all_consuming(ws(or_query))(s) {
+            Ok((_, query)) => Ok(query),
+            Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => {
+                let near = if e.input.is_empty() { "end of query" } else { e.input };
+                Err(format!("could not parse query at column {}, near {}", s.len() - e.input.len() + 1, near))
+            },
+            Err(nom::Err::Incomplete(_)) => Err("incomplete query".to_owned()),
+        }
+    }
+}
+
+fn ws<'a, O, F: FnMut(&'a str) -> IResult<&'a str, O>>(f: F) -> impl FnMut(&'a str) -> IResult<&'a str, O> {
+    delimited(multispace0, f, multispace0)
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+// colours may be hyphenated, like sky-blue
+fn is_colour(c: char) -> bool {
+    is_word(c) || c == '-'
+}
+
+// so `and` doesn't match the start of `android`
+fn keyword<'a>(word: &'static str) -> impl FnMut(&'a str) -> IResult<&'a str, &'a str> {
+    ws(terminated(tag(word), not(satisfy(is_word))))
+}
+
+fn or_query(i: &str) -> IResult<&str, Query> {
+    let (i, first) = and_query(i)?;
+    let (i, rest) = many0(preceded(keyword("or"), and_query))(i)?;
+
+    Ok((i, rest.into_iter().fold(first, |acc, x| Query::Or(Box::new(acc), Box::new(x)))))
+}
+
+fn and_query(i: &str) -> IResult<&str, Query> {
+    let (i, first) = unary_query(i)?;
+    let (i, rest) = many0(preceded(keyword("and"), unary_query))(i)?;
+
+    Ok((i, rest.into_iter().fold(first, |acc, x| Query::And(Box::new(acc), Box::new(x)))))
+}
+
+// a bracket could open either a nested query or a sum like `(max(red) + 1) * 2 > 5`,
+// alt falls back to the comparison when the nested query doesn't parse
+fn unary_query(i: &str) -> IResult<&str, Query> {
+    alt((
+        map(preceded(keyword("not"), unary_query), |x| Query::Not(Box::new(x))),
+        delimited(ws(char('(')), or_query, ws(char(')'))),
+        map(tuple((sum, comparison, sum)), |(lhs, op, rhs)| Query::Compare(lhs, op, rhs)),
+    ))(i)
+}
+
+fn comparison(i: &str) -> IResult<&str, Comparison> {
+    ws(alt((
+        value(Comparison::LessEqual, tag("<=")),
+        value(Comparison::GreaterEqual, tag(">=")),
+        value(Comparison::NotEqual, tag("!=")),
+        value(Comparison::Equal, tag("==")),
+        value(Comparison::Equal, tag("=")),
+        value(Comparison::Less, tag("<")),
+        value(Comparison::Greater, tag(">")),
+    )))(i)
+}
+
+fn sum(i: &str) -> IResult<&str, Value> {
+    let (i, first) = product(i)?;
+    let (i, rest) = many0(pair(ws(alt((char('+'), char('-')))), product))(i)?;
+
+    Ok((i, rest.into_iter().fold(first, |acc, (op, x)| match op {
+        '+' => Value::Add(Box::new(acc), Box::new(x)),
+        _ => Value::Subtract(Box::new(acc), Box::new(x)),
+    })))
+}
+
+fn product(i: &str) -> IResult<&str, Value> {
+    let (i, first) = atom(i)?;
+    let (i, rest) = many0(preceded(ws(char('*')), atom))(i)?;
+
+    Ok((i, rest.into_iter().fold(first, |acc, x| Value::Multiply(Box::new(acc), Box::new(x)))))
+}
+
+fn atom(i: &str) -> IResult<&str, Value> {
+    alt((
+        map(map_res(ws(digit1), str::parse::<i64>), Value::Number),
+        map(pair(aggregate, delimited(ws(char('(')), ws(take_while1(is_colour)), ws(char(')')))), |(aggregate, colour)| {
+            Value::Aggregate(aggregate, colour.to_owned())
+        }),
+        value(Value::Id, keyword("id")),
+        value(Value::Reveals, keyword("reveals")),
+        delimited(ws(char('(')), sum, ws(char(')'))),
+    ))(i)
+}
+
+fn aggregate(i: &str) -> IResult<&str, Aggregate> {
+    alt((
+        value(Aggregate::Max, keyword("max")),
+        value(Aggregate::Min, keyword("min")),
+        value(Aggregate::Sum, keyword("sum")),
+        value(Aggregate::Count, keyword("count")),
+    ))(i)
+}
@@ -0,0 +1,322 @@
+use std::{fmt::Display, error::Error, ops::{Index, IndexMut}};
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Coordinate {
+    pub row: usize,
+    pub column: usize
+}
+
+impl From<(usize, usize)> for Coordinate {
+    fn from(value: (usize, usize)) -> Self {
+        Coordinate { row: value.0, column: value.1 }
+    }
+}
+
+#[allow(dead_code)]
+const ORTHOGONAL: [(isize, isize); 4] = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+const SURROUNDING: [(isize, isize); 8] = [(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0), (1, 1)];
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum GridError {
+    Ragged { row: usize, expected: usize, found: usize },
+}
+
+impl Display for GridError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Ragged { row, expected, found } => write!(f, "row {} has {} columns, expected {}", row, found, expected),
+        }
+    }
+}
+
+impl Error for GridError {}
+
+impl From<GridError> for std::io::Error {
+    fn from(value: GridError) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, value)
+    }
+}
+
+// row-major rectangular grid
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Result<Grid<T>, GridError> {
+        let height = rows.len();
+        let width = rows.first().map(|x| x.len()).unwrap_or(0);
+        let mut cells = Vec::with_capacity(width * height);
+
+        for (row, values) in rows.into_iter().enumerate() {
+            if values.len() != width {
+                return Err(GridError::Ragged { row, expected: width, found: values.len() });
+            }
+            cells.extend(values);
+        }
+
+        Ok(Grid { width, height, cells })
+    }
+
+    pub fn parse<F: Fn(char) -> T>(text: &str, f: F) -> Result<Grid<T>, GridError> {
+        Grid::from_rows(text.lines().map(|line| line.chars().map(&f).collect()).collect())
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn in_bounds(&self, coord: Coordinate) -> bool {
+        coord.row < self.height && coord.column < self.width
+    }
+
+    fn offset(&self, coord: Coordinate, by: (isize, isize)) -> Option<Coordinate> {
+        let row = coord.row.checked_add_signed(by.0)?;
+        let column = coord.column.checked_add_signed(by.1)?;
+        let moved = Coordinate { row, column };
+
+        self.in_bounds(moved).then_some(moved)
+    }
+
+    // all eight surrounding cells that are inside the grid
+    pub fn neighbours8(&self, coord: Coordinate) -> impl Iterator<Item = Coordinate> + '_ {
+        SURROUNDING.iter().filter_map(move |by| self.offset(coord, *by))
+    }
+
+    pub fn coordinates(&self) -> impl Iterator<Item = Coordinate> + '_ {
+        (0..self.height).flat_map(move |row| (0..self.width).map(move |column| Coordinate { row, column }))
+    }
+
+    pub fn row(&self, row: usize) -> &[T] {
+        &self.cells[row * self.width..(row + 1) * self.width]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> + '_ {
+        (0..self.height).map(move |row| self.row(row))
+    }
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn filled(width: usize, height: usize, value: T) -> Grid<T> {
+        Grid { width, height, cells: vec![value; width * height] }
+    }
+
+    // short rows are padded with fill out to the longest row instead of being an error
+    pub fn parse_padded<F: Fn(char) -> T>(text: &str, f: F, fill: T) -> Grid<T> {
+        let mut rows: Vec<Vec<T>> = text.lines().map(|line| line.chars().map(&f).collect()).collect();
+        let width = rows.iter().map(|x| x.len()).max().unwrap_or(0);
+        for row in rows.iter_mut() {
+            row.resize(width, fill.clone());
+        }
+
+        Grid::from_rows(rows).expect("rows were padded to the same width")
+    }
+}
+
+// general grid helpers that no puzzle needs yet
+#[allow(dead_code)]
+impl<T> Grid<T> {
+    pub fn get(&self, coord: Coordinate) -> Option<&T> {
+        if self.in_bounds(coord) {
+            Some(&self.cells[coord.row * self.width + coord.column])
+        } else {
+            None
+        }
+    }
+
+    // up, left, right, down - whichever are inside the grid
+    pub fn neighbours4(&self, coord: Coordinate) -> impl Iterator<Item = Coordinate> + '_ {
+        ORTHOGONAL.iter().filter_map(move |by| self.offset(coord, *by))
+    }
+
+    pub fn column(&self, column: usize) -> impl Iterator<Item = &T> + '_ {
+        (0..self.height).map(move |row| &self[Coordinate { row, column }])
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T> + '_> + '_ {
+        (0..self.width).map(move |column| self.column(column))
+    }
+
+    // a grid with rows but no columns (or the other way round) has no cells,
+    // so it has no diagonals either
+    fn extent(&self) -> (usize, usize) {
+        if self.cells.is_empty() {
+            (0, 0)
+        } else {
+            (self.width, self.height)
+        }
+    }
+
+    // top-left to bottom-right diagonals, starting from the bottom-left corner
+    pub fn diagonals(&self) -> impl Iterator<Item = Vec<&T>> + '_ {
+        let (width, height) = self.extent();
+        let starts = (0..height).rev().map(|row| Coordinate { row, column: 0 })
+            .chain((1..width).map(|column| Coordinate { row: 0, column }));
+
+        starts.map(move |start| self.walk(start, (1, 1)).collect())
+    }
+
+    // top-right to bottom-left diagonals, starting from the top-left corner
+    pub fn anti_diagonals(&self) -> impl Iterator<Item = Vec<&T>> + '_ {
+        let (width, height) = self.extent();
+        let starts = (0..width).map(|column| Coordinate { row: 0, column })
+            .chain((1..height).map(move |row| Coordinate { row, column: width - 1 }));
+
+        starts.map(move |start| self.walk(start, (1, -1)).collect())
+    }
+
+    fn walk(&self, start: Coordinate, by: (isize, isize)) -> impl Iterator<Item = &T> + '_ {
+        std::iter::successors(self.get(start).map(|_| start), move |x| self.offset(*x, by)).map(move |x| &self[x])
+    }
+}
+
+#[allow(dead_code)]
+impl<T: Clone> Grid<T> {
+    pub fn transpose(&self) -> Grid<T> {
+        Grid {
+            width: self.height,
+            height: self.width,
+            cells: self.columns().flatten().cloned().collect(),
+        }
+    }
+
+    pub fn rotate_clockwise(&self) -> Grid<T> {
+        let cells = (0..self.width)
+            .flat_map(|column| (0..self.height).rev().map(move |row| Coordinate { row, column }))
+            .map(|x| self[x].clone())
+            .collect();
+
+        Grid { width: self.height, height: self.width, cells }
+    }
+
+    pub fn rotate_counter_clockwise(&self) -> Grid<T> {
+        let cells = (0..self.width).rev()
+            .flat_map(|column| (0..self.height).map(move |row| Coordinate { row, column }))
+            .map(|x| self[x].clone())
+            .collect();
+
+        Grid { width: self.height, height: self.width, cells }
+    }
+}
+
+impl Grid<char> {
+    pub fn parse_chars(text: &str) -> Result<Grid<char>, GridError> {
+        Grid::parse(text, |x| x)
+    }
+}
+
+impl<T> Index<Coordinate> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, index: Coordinate) -> &Self::Output {
+        assert!(self.in_bounds(index), "coordinate out of bounds");
+        &self.cells[index.row * self.width + index.column]
+    }
+}
+
+impl<T> IndexMut<Coordinate> for Grid<T> {
+    fn index_mut(&mut self, index: Coordinate) -> &mut Self::Output {
+        assert!(self.in_bounds(index), "coordinate out of bounds");
+        &mut self.cells[index.row * self.width + index.column]
+    }
+}
+
+impl<T: Display> Display for Grid<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for row in self.rows() {
+            for cell in row {
+                write!(f, "{}", cell)?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // two rows of three, so rows and columns can't be mixed up
+    fn wide() -> Grid<char> {
+        Grid::parse_chars("abc\ndef").unwrap()
+    }
+
+    // no columns but more than one row
+    fn hollow() -> Grid<char> {
+        Grid::from_rows(vec![Vec::new(), Vec::new()]).unwrap()
+    }
+
+    fn strings<'a, I: Iterator<Item = Vec<&'a char>>>(lines: I) -> Vec<String> {
+        lines.map(|x| x.into_iter().collect()).collect()
+    }
+
+    #[test]
+    fn get() {
+        let grid = wide();
+        assert_eq!(grid.get(Coordinate { row: 1, column: 2 }), Some(&'f'));
+        assert_eq!(grid.get(Coordinate { row: 2, column: 0 }), None);
+        assert_eq!(grid.get(Coordinate { row: 0, column: 3 }), None);
+        assert_eq!(hollow().get(Coordinate { row: 0, column: 0 }), None);
+    }
+
+    #[test]
+    fn neighbours4() {
+        let grid = wide();
+        let around = |row, column| grid.neighbours4(Coordinate { row, column }).map(|x| grid[x]).collect::<String>();
+        assert_eq!(around(0, 0), "bd");
+        assert_eq!(around(0, 1), "ace");
+        assert_eq!(around(1, 2), "ce");
+        assert_eq!(hollow().neighbours4(Coordinate { row: 0, column: 0 }).count(), 0);
+    }
+
+    #[test]
+    fn columns() {
+        let grid = wide();
+        assert_eq!(grid.column(1).collect::<String>(), "be");
+        assert_eq!(grid.columns().map(|x| x.collect::<String>()).collect::<Vec<_>>(), ["ad", "be", "cf"]);
+        assert_eq!(hollow().columns().count(), 0);
+    }
+
+    #[test]
+    fn diagonals() {
+        assert_eq!(strings(wide().diagonals()), ["d", "ae", "bf", "c"]);
+        assert_eq!(strings(wide().transpose().diagonals()), ["c", "bf", "ae", "d"]);
+        assert_eq!(hollow().diagonals().count(), 0);
+    }
+
+    #[test]
+    fn anti_diagonals() {
+        assert_eq!(strings(wide().anti_diagonals()), ["a", "bd", "ce", "f"]);
+        assert_eq!(strings(wide().transpose().anti_diagonals()), ["a", "db", "ec", "f"]);
+        assert_eq!(hollow().anti_diagonals().count(), 0);
+    }
+
+    #[test]
+    fn transpose() {
+        assert_eq!(wide().transpose().to_string(), "ad\nbe\ncf\n");
+        assert_eq!(wide().transpose().transpose(), wide());
+
+        let flipped = hollow().transpose();
+        assert_eq!((flipped.width(), flipped.height()), (2, 0));
+    }
+
+    #[test]
+    fn rotations() {
+        assert_eq!(wide().rotate_clockwise().to_string(), "da\neb\nfc\n");
+        assert_eq!(wide().rotate_counter_clockwise().to_string(), "cf\nbe\nad\n");
+        assert_eq!(wide().rotate_clockwise().rotate_counter_clockwise(), wide());
+
+        for rotated in [hollow().rotate_clockwise(), hollow().rotate_counter_clockwise()] {
+            assert_eq!((rotated.width(), rotated.height()), (2, 0));
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use std::{io::BufReader, fs::File};
+use std::{io::BufReader, fs::File, process::ExitCode};
 
 use clap::Parser;
 
@@ -12,6 +12,10 @@ mod day_seven;
 mod day_eight;
 mod day_nine;
 
+mod config;
+mod grid;
+mod number_theory;
+
 #[derive(Parser)]
 #[command(version = "0.0.1", author = "Evan Merlock")]
 struct AdventOfCodeOptions {
@@ -21,36 +25,58 @@ struct AdventOfCodeOptions {
     day: u32,
     #[clap(short, long)]
     question: u32,
+    #[command(flatten)]
+    day_one: day_one::Options,
+    #[command(flatten)]
+    day_two: day_two::Options,
+    #[command(flatten)]
+    day_three: day_three::Options,
+    #[command(flatten)]
+    day_four: day_four::Options,
+    #[command(flatten)]
+    day_eight: day_eight::Options,
+    #[command(flatten)]
+    day_nine: day_nine::Options,
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
+// errors are printed with their Display messages, which are written for people
+fn main() -> ExitCode {
     let options = AdventOfCodeOptions::parse();
 
-    let in_file: BufReader<File> = BufReader::new(File::open(options.in_file)?);
+    match run(&options) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        },
+    }
+}
+
+fn run(options: &AdventOfCodeOptions) -> Result<(), Box<dyn std::error::Error>> {
+    let in_file: BufReader<File> = BufReader::new(File::open(&options.in_file)?);
 
     match (options.day, options.question) {
-        (1, 1) => day_one::question_one(in_file)?,
-        (1, 2) => day_one::question_two(in_file)?,
-        (2, 1) => day_two::question_one(in_file)?,
-        (2, 2) => day_two::question_two(in_file)?,
-        (3, 1) => day_three::question_one(in_file)?,
-        (3, 2) => day_three::question_two(in_file)?,
-        (4, 1) => day_four::question_one(in_file)?,
-        (4, 2) => day_four::question_two(in_file)?,
+        (1, 1) => day_one::question_one(in_file, &options.day_one)?,
+        (1, 2) => day_one::question_two(in_file, &options.day_one)?,
+        (2, 1) => day_two::question_one(in_file, &options.day_two)?,
+        (2, 2) => day_two::question_two(in_file, &options.day_two)?,
+        (3, 1) => day_three::question_one(in_file, &options.day_three)?,
+        (3, 2) => day_three::question_two(in_file, &options.day_three)?,
+        (4, 1) => day_four::question_one(in_file, &options.day_four)?,
+        (4, 2) => day_four::question_two(in_file, &options.day_four)?,
         (5, 1) => day_five::question_one(in_file)?,
         (5, 2) => day_five::question_two(in_file)?,
         (6, 1) => day_six::question_one(in_file)?,
         (6, 2) => day_six::question_two(in_file)?,
         (7, 1) => day_seven::question_one(in_file)?,
         (7, 2) => day_seven::question_two(in_file)?,
-        (8, 1) => day_eight::question_one(in_file)?,
-        (8, 2) => day_eight::question_two(in_file)?,
-        (9, 1) => day_nine::question_one(in_file)?,
-        (9, 2) => day_nine::question_two(in_file)?,
+        (8, 1) => day_eight::question_one(in_file, &options.day_eight)?,
+        (8, 2) => day_eight::question_two(in_file, &options.day_eight)?,
+        (9, 1) => day_nine::question_one(in_file, &options.day_nine)?,
+        (9, 2) => day_nine::question_two(in_file, &options.day_nine)?,
 
         _ => panic!("Invalid day/question combination!")
     };
 
     Ok(())
-
 }
\ No newline at end of file
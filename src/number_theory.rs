@@ -0,0 +1,172 @@
+use std::{fmt::Display, error::Error};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum CrtError {
+    // the congruences disagree where their moduli overlap
+    NoSolution,
+    Overflow,
+    InvalidModulus(i128),
+}
+
+impl Display for CrtError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoSolution => write!(f, "congruences have no common solution"),
+            Self::Overflow => write!(f, "combined modulus does not fit in an i128"),
+            Self::InvalidModulus(m) => write!(f, "modulus {} is not positive", m),
+        }
+    }
+}
+
+impl Error for CrtError {}
+
+pub fn gcd(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+
+    a
+}
+
+// None when the result does not fit in a u128
+pub fn checked_lcm(a: u128, b: u128) -> Option<u128> {
+    if a == 0 || b == 0 {
+        return Some(0);
+    }
+
+    (a / gcd(a, b)).checked_mul(b)
+}
+
+// lcm of nothing is 1, so an empty iterator is fine
+pub fn lcm<I: IntoIterator<Item = u128>>(nums: I) -> Option<u128> {
+    nums.into_iter().try_fold(1, checked_lcm)
+}
+
+// returns (g, x, y) such that a * x + b * y = g = gcd(a, b)
+pub fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_x, mut x) = (1, 0);
+    let (mut old_y, mut y) = (0, 1);
+
+    while r != 0 {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_x, x) = (x, old_x - q * x);
+        (old_y, y) = (y, old_y - q * y);
+    }
+
+    if old_r < 0 {
+        (-old_r, -old_x, -old_y)
+    } else {
+        (old_r, old_x, old_y)
+    }
+}
+
+// inverse of a modulo m in 0..m, if a and m are coprime
+pub fn mod_inverse(a: i128, m: i128) -> Option<i128> {
+    if m <= 0 {
+        return None;
+    }
+
+    let (g, x, _) = extended_gcd(a.rem_euclid(m), m);
+    if g != 1 {
+        return None;
+    }
+
+    Some(x.rem_euclid(m))
+}
+
+// solves x = r (mod m) for every (r, m) pair, moduli need not be coprime.
+// returns (x, lcm of moduli) with x in 0..lcm
+pub fn crt<I: IntoIterator<Item = (i128, i128)>>(congruences: I) -> Result<(i128, i128), CrtError> {
+    let mut result: (i128, i128) = (0, 1);
+
+    for (r2, m2) in congruences {
+        if m2 <= 0 {
+            return Err(CrtError::InvalidModulus(m2));
+        }
+
+        let (r1, m1) = result;
+        let r2 = r2.rem_euclid(m2);
+
+        let (g, _, _) = extended_gcd(m1, m2);
+        let diff = r2 - r1;
+        if diff % g != 0 {
+            return Err(CrtError::NoSolution);
+        }
+
+        let m2_g = m2 / g;
+        let combined = (m1 / g).checked_mul(m2).ok_or(CrtError::Overflow)?;
+
+        // m1 / g and m2 / g are coprime so this always exists
+        let inv = mod_inverse(m1 / g, m2_g).expect("reduced moduli are coprime");
+        let k = (diff / g).rem_euclid(m2_g).checked_mul(inv).ok_or(CrtError::Overflow)? % m2_g;
+
+        let x = m1.checked_mul(k).and_then(|x| x.checked_add(r1)).ok_or(CrtError::Overflow)?;
+
+        result = (x.rem_euclid(combined), combined);
+    }
+
+    Ok(result)
+}
+
+// an exact fraction, always stored reduced with a positive denominator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rational {
+    numerator: i128,
+    denominator: i128,
+}
+
+impl Rational {
+    pub fn new(numerator: i128, denominator: i128) -> Option<Rational> {
+        if denominator == 0 {
+            return None;
+        }
+
+        let g = gcd(numerator.unsigned_abs(), denominator.unsigned_abs()).max(1) as i128;
+        let sign = denominator.signum();
+
+        Some(Rational {
+            numerator: sign.checked_mul(numerator / g)?,
+            denominator: sign.checked_mul(denominator / g)?,
+        })
+    }
+
+    pub fn integer(value: i128) -> Rational {
+        Rational { numerator: value, denominator: 1 }
+    }
+
+    pub fn numerator(&self) -> i128 {
+        self.numerator
+    }
+
+    pub fn denominator(&self) -> i128 {
+        self.denominator
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.numerator == 0
+    }
+
+    pub fn checked_add(self, other: Rational) -> Option<Rational> {
+        let g = gcd(self.denominator as u128, other.denominator as u128) as i128;
+        let lhs = self.numerator.checked_mul(other.denominator / g)?;
+        let rhs = other.numerator.checked_mul(self.denominator / g)?;
+
+        Rational::new(lhs.checked_add(rhs)?, (self.denominator / g).checked_mul(other.denominator)?)
+    }
+
+    pub fn checked_neg(self) -> Option<Rational> {
+        Some(Rational { numerator: self.numerator.checked_neg()?, denominator: self.denominator })
+    }
+}
+
+impl Display for Rational {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.denominator == 1 {
+            write!(f, "{}", self.numerator)
+        } else {
+            write!(f, "{}/{}", self.numerator, self.denominator)
+        }
+    }
+}